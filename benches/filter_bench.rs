@@ -0,0 +1,58 @@
+// How fast are the IIR filters
+// Compare biquad topologies and the SOS cascade
+// and direct FIR with the FFT convolution
+
+#[macro_use]
+extern crate criterion;
+
+use criterion::Criterion;
+
+use dsp::filter::biquad::{BiquadFilter, Topology, Width};
+use dsp::filter::convolution::{FftConvolver, Method, PartitionedConvolver};
+use dsp::filter::design::iir::{self, Band, Prototype};
+use dsp::filter::fir::FirFilter;
+use dsp::filter::sos::SosFilter;
+use dsp::runtime::node::ProcessNode;
+
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let input: Vec<f32> = (0..1024).map(|i| (i as f32 * 0.1).sin()).collect();
+    let mut output = vec![0.0; 1024];
+
+    let mut df1 = BiquadFilter::lowpass(1_000.0, Width::Q(0.7), 44_100).unwrap()
+        .with_topology(Topology::DirectForm1);
+    c.bench_function("Biquad DF1", |b| {
+        b.iter(|| df1.process_buffer(&input, &mut output))
+    });
+
+    let mut df2t = BiquadFilter::lowpass(1_000.0, Width::Q(0.7), 44_100).unwrap()
+        .with_topology(Topology::TransposedDirectForm2);
+    c.bench_function("Biquad DF2T", |b| {
+        b.iter(|| df2t.process_buffer(&input, &mut output))
+    });
+
+    let zpk = iir::digital(Prototype::Butterworth, 8, Band::Lowpass(1_000.0), 44_100).unwrap();
+    let mut sos = SosFilter::from_zpk(&zpk).unwrap();
+    c.bench_function("SOS 8th order", |b| {
+        b.iter(|| sos.process_buffer(&input, &mut output))
+    });
+
+    let taps: Vec<f32> = (0..512).map(|i| (-(i as f32) / 100.0).exp()).collect();
+    let mut fir = FirFilter::new(&taps).unwrap();
+    c.bench_function("FIR 512 taps", |b| {
+        b.iter(|| fir.process_buffer(&input, &mut output))
+    });
+
+    let mut ols = FftConvolver::new(&taps, 1024, Method::OverlapSave).unwrap();
+    c.bench_function("Overlap-save 512 taps", |b| {
+        b.iter(|| ols.process_buffer(&input, &mut output))
+    });
+
+    let mut partitioned = PartitionedConvolver::new(&taps, 128).unwrap();
+    c.bench_function("Partitioned 512 taps", |b| {
+        b.iter(|| partitioned.process_buffer(&input, &mut output))
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
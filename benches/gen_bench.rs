@@ -7,6 +7,7 @@ extern crate criterion;
 use criterion::Criterion;
 
 use dsp::core::generator::*;
+use dsp::runtime::node::SourceNode;
 
 
 fn criterion_benchmark(c: &mut Criterion) {
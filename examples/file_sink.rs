@@ -1,6 +1,7 @@
 use clap::Parser;
 use dsp::core::generator::Sine;
 use dsp::core::file::FileSink;
+use dsp::runtime::flowgraph::Flowgraph;
 
 
 const SIGNAL_LENGTH: usize = 512;
@@ -18,12 +19,8 @@ struct Args {
 
 fn main() {
     let args = Args::parse();
-    let mut generator = Sine::new(args.freq, args.sample_rate);
-    let mut file_sink = FileSink::new("target/example.data");
-    let mut buffer = vec![0.0; SIGNAL_LENGTH];
-
-    for _ in 1..10 {
-        let _ = generator.write_buffer(&mut buffer);
-        let _ = file_sink.read_buffer(&buffer);
-    }
-}
\ No newline at end of file
+    let mut fg = Flowgraph::new(SIGNAL_LENGTH);
+    let signal = fg.add_source(Sine::new(args.freq, args.sample_rate));
+    fg.add_sink(signal, FileSink::new("target/example.data").unwrap());
+    fg.run_for(9).unwrap();
+}
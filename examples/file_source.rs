@@ -1,12 +1,13 @@
 use gnuplot::{Figure, Color, AxesCommon};
 use dsp::core::file::FileSource;
+use dsp::runtime::node::SourceNode;
 
 
 const SAMPLE_RATE: usize = 512;
 
 
 fn main() {
-    let mut file_source = FileSource::new("target/example.data");
+    let mut file_source = FileSource::new("target/example.data").unwrap();
     let mut buffer = vec![0.0; SAMPLE_RATE];
     let _ = file_source.write_buffer(&mut buffer);
 
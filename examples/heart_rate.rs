@@ -1,6 +1,9 @@
 use std::fs::File;
 use gnuplot::{Figure, Color, AxesCommon};
 use dsp::core::correlation::*;
+use dsp::filter::design::iir::{self, Band};
+use dsp::filter::filtfilt::{filtfilt, Padding};
+use dsp::runtime::node::ProcessNode;
 
 
 const SAMPLE_RATE: usize = 100;
@@ -21,7 +24,10 @@ fn read_mat_file(fname: &str) -> Vec<f32> {
 
 
 fn main() {
-    let buffer = read_mat_file("examples/assets/ppg.mat");
+    let raw = read_mat_file("examples/assets/ppg.mat");
+    // Remove baseline wander and noise without shifting the pulses
+    let filter = iir::butterworth(2, Band::Bandpass(0.5, 5.0), SAMPLE_RATE).unwrap();
+    let buffer = filtfilt(&filter, &raw, Padding::Odd).unwrap();
     let mut ac = AutoCorrelation::new(WINDOW_SIZE); 
     let mut corr_buffer = vec![0.; buffer.len() - WINDOW_SIZE];
     let _ = ac.process_buffer(&buffer, &mut corr_buffer);
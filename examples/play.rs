@@ -1,5 +1,6 @@
 
 use dsp::core::generator::Sine;
+use dsp::runtime::node::SourceNode;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 
 
@@ -26,7 +27,7 @@ fn main() {
     let stream = device.build_output_stream(
         &config,
         move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
-            generator.write_buffer(data);
+            let _ = generator.write_buffer(data);
         },
         move |err| {panic!("cpal stream error {:?}", err);},
         None
@@ -0,0 +1,43 @@
+use gnuplot::{Figure, Color, AxesCommon};
+use clap::Parser;
+use dsp::filter::analysis::FilterResponse;
+use dsp::filter::design::iir::{self, Band, Prototype};
+
+
+const SAMPLE_RATE: usize = 48_000;
+const NUM_POINTS: usize = 1_000;
+
+
+#[derive(Parser, Debug)]
+struct Args {
+    /// Cutoff frequency
+    #[clap(short, long, default_value_t = 1_000.0)]
+    cutoff: f32,
+    /// Filter order
+    #[clap(short, long, default_value_t = 4)]
+    order: usize,
+}
+
+fn main() {
+    let args = Args::parse();
+    let prototypes = [
+        ("Butterworth", Prototype::Butterworth, "red"),
+        ("Chebyshev I", Prototype::Chebyshev1 { ripple: 1.0 }, "blue"),
+        ("Elliptic", Prototype::Elliptic { ripple: 1.0, attenuation: 60.0 }, "green"),
+        ("Bessel", Prototype::Bessel, "black"),
+    ];
+    let max_freq = 4.0 * args.cutoff;
+    let freqs: Vec<f32> = (0..NUM_POINTS).map(|i| i as f32 * max_freq / NUM_POINTS as f32).collect();
+
+    let mut fg = Figure::new();
+    fg.set_title("Filter response");
+    let axes = fg.axes2d();
+    for (name, prototype, color) in prototypes {
+        let filter = iir::design(prototype, args.order, Band::Lowpass(args.cutoff), SAMPLE_RATE).unwrap();
+        let gain: Vec<f32> = filter.magnitude_db(&freqs, SAMPLE_RATE).iter().map(|g| g.max(-100.0)).collect();
+        axes.lines(&freqs, &gain, &[gnuplot::Caption(name), Color(color)]);
+    }
+    axes.set_x_label("Freq (Hz)", &[]);
+    axes.set_y_label("Gain (dB)", &[]);
+    fg.show().unwrap();
+}
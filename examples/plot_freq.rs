@@ -1,7 +1,9 @@
 use gnuplot::{Figure, Color, AxesCommon};
 use clap::Parser;
 use dsp::num_complex::Complex32;
-use dsp::core::{generator::Sine, fft::*, complex::*};
+use dsp::core::{generator::Sine, fft::*};
+use dsp::runtime::node::{SourceNode, ProcessNode};
+use dsp::spectrum;
 
 
 const FRAME_SIZE: usize = 8_192;
@@ -19,25 +21,21 @@ struct Args {
 fn main() {
     let args = Args::parse();
     let mut generator = Sine::new(args.freq, SAMPLE_RATE);
-    let mut r2c = RealToComplex::new();
-    let mut fft = ForwardFFT::new(FRAME_SIZE, WindowType::Hamming);
+    let mut fft = RealFFT::new(FRAME_SIZE, WindowType::Hamming)
+        .with_normalization(Normalization::Amplitude);
     let mut buffer1 = vec![0.0; FRAME_SIZE];
-    let mut buffer2 = vec![Complex32::new(0., 0.); FRAME_SIZE];
-    let mut buffer3 = vec![Complex32::new(0., 0.); FRAME_SIZE];
+    let mut buffer2 = vec![Complex32::new(0., 0.); fft.output_size(FRAME_SIZE)];
 
     let _ = generator.write_buffer(&mut buffer1);
-    let _ = r2c.process_buffer(&buffer1, &mut buffer2);
-    let _ = fft.process_buffer(&buffer2, &mut buffer3);
+    let _ = fft.process_buffer(&buffer1, &mut buffer2);
 
-    // Plot signal with ms as units
-    let res = SAMPLE_RATE as f32 / FRAME_SIZE as f32;
-    let n = (MAX_FREQ / res) as usize;
-    let idx: Vec< f32> = (0..n).map(|i| i as f32 * res).collect();
-    let buf: Vec<f32> = buffer3.iter().map(|e| e.norm()).collect();
-    let ref_point: f32 = buf.iter().sum();
-    let data: Vec<f32> = buf[0..n].iter()
-        .map(|e| 20. * f32::log10(2. * e / ref_point))
-        .collect(); 
+    // Plot frequencies up to MAX_FREQ. Real sine has half of the amplitude in the positive frequencies
+    let idx: Vec<f32> = spectrum::rfft_freqs(FRAME_SIZE, SAMPLE_RATE).into_iter()
+        .take_while(|f| *f < MAX_FREQ)
+        .collect();
+    let data: Vec<f32> = buffer2[0..idx.len()].iter()
+        .map(|e| spectrum::dbfs(&(e * 2.), 1.))
+        .collect();
     let mut fg = Figure::new();
     fg.set_title("Frequency plot");
     let axes = fg.axes2d();
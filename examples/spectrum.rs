@@ -2,7 +2,8 @@ use gnuplot::*;
 use clap::Parser;
 use dsp::num_complex::Complex32;
 use dsp::runtime::node::*;
-use dsp::node::{generator::*, fft::*, complex::*};
+use dsp::core::{generator::*, stft::*, complex::*};
+use dsp::window;
 
 
 const SIGNAL_LENGTH: usize = 10*256;
@@ -22,10 +23,10 @@ struct Args {
 /// Parse command line arguments
 /// Create signal
 fn create_generator(gen_name: &str, freq: f32, sample_rate:usize) -> Box<dyn SourceNode<f32>> {
-    match gen_name.as_ref() {
+    match gen_name {
         "sawtooth"  => Box::new(Sawtooth::new(freq, sample_rate)),
         "square"    => Box::new(Square::new(freq, sample_rate)),
-        "noise"     => Box::new(Noise::new(0.1)),
+        "noise"     => Box::new(Noise::new(0.1).unwrap()),
         "chirp"     => Box::new(Chirp::new(4.0, 1.0, 10.0, sample_rate)),
         _           => Box::new(Sine::new(freq, sample_rate)),
     }
@@ -34,34 +35,31 @@ fn create_generator(gen_name: &str, freq: f32, sample_rate:usize) -> Box<dyn Sou
 
 fn main() {
     let args = Args::parse();
-    let num_spectrums = 10;
-    let window_size = SIGNAL_LENGTH / num_spectrums;
+    let window_size = 256;
     let mut generator = create_generator(&args.gen_name, args.freq, window_size);
-    let mut r2c = RealToComplex::new();
-    let mut c2r = ComplexToReal::new();
-    let mut fft = ForwardFFT::new(window_size, WindowType::Hamming);
+    let mut c2r = ComplexToReal::with_mode(ComplexMode::Db);
+    // Frames overlap by half of the window
+    let mut stft = Stft::new(window_size, window_size / 2, window::hamming(window_size)).unwrap();
     let mut buffer1 = vec![0.0; SIGNAL_LENGTH];
-    let mut buffer2 = vec![Complex32::new(0., 0.); SIGNAL_LENGTH];
-    let mut buffer3 = vec![Complex32::new(0., 0.); window_size];
-    let mut buffer4 = vec![0.; window_size];
+    let mut buffer2 = vec![Complex32::new(0., 0.); stft.output_size(SIGNAL_LENGTH)];
+    let mut buffer3 = vec![0.; buffer2.len()];
     
     let _ = generator.write_buffer(&mut buffer1);
-    let _ = r2c.process_buffer(&buffer1, &mut buffer2);
+    let n = stft.process_buffer(&buffer1, &mut buffer2).unwrap();
+    let _ = c2r.process_buffer(&buffer2[..n], &mut buffer3);
 
-    // Split signal into frames
-    let ps: Vec<f32> = (0..num_spectrums).flat_map(|i| {
-        let (x1, x2) = (i*window_size, ((i+1)*window_size));
-        let _ = fft.process_buffer(&buffer2[x1..x2], &mut buffer3);
-        let _ = c2r.process_buffer(&buffer3, &mut buffer4);
-        buffer4[0..window_size/2].to_owned()
-    }).collect();
+    // Drop Nyquist bin from each frame
+    let num_spectrums = n / stft.num_bins();
+    let ps: Vec<f32> = buffer3[..n].chunks(stft.num_bins())
+        .flat_map(|frame| frame[..window_size/2].to_owned())
+        .collect();
 
     plot_spectrogram(window_size/2, num_spectrums, &ps, window_size as f32/2.0);
 }
 
-fn plot_spectrogram(height: usize, width: usize, data: &Vec<f32>, max_freq: f32) {
+fn plot_spectrogram(height: usize, width: usize, data: &[f32], max_freq: f32) {
     let mut transposed = vec![0.0; height*width];
-    transpose::transpose(&data, &mut transposed, height, width);
+    transpose::transpose(data, &mut transposed, height, width);
 
 	let mut fg = Figure::new();
     fg.axes2d().image(
@@ -69,7 +67,7 @@ fn plot_spectrogram(height: usize, width: usize, data: &Vec<f32>, max_freq: f32)
 		height,
         width,
 		Some((0.0, 0.0, SIGNAL_LENGTH as f64, max_freq as f64)),
-		&[Caption("Frequency in Hz.")],
+		&[Caption("Power in dB. Frequency in Hz.")],
 	);
 	fg.show().unwrap();    
 }
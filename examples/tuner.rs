@@ -1,5 +1,7 @@
 use std::env;
+use dsp::Error;
 use dsp::audio::AudioFileSource;
+use dsp::runtime::node::{SourceNode, ProcessNode};
 use gnuplot::{Figure, Color, AxesCommon};
 use dsp::core::{fft::*, complex::*};
 use dsp::num_complex::Complex32;
@@ -16,7 +18,7 @@ const MAX_FREQ: f32 = 1_000.;
 fn main() {
     let file_path = env::args().nth(1).unwrap_or("examples/assets/sine_440hz.wav".to_string());
 
-    let mut audio_src = AudioFileSource::new(&file_path);
+    let mut audio_src = AudioFileSource::new(&file_path).unwrap();
     let mut r2c = RealToComplex::new();
     let mut fft = ForwardFFT::new(FRAME_SIZE, WindowType::Hamming);
     let mut buffer1 = vec![0.; FRAME_SIZE];
@@ -24,10 +26,15 @@ fn main() {
     let mut buffer3 = vec![Complex32::new(0., 0.); FRAME_SIZE];
 
 
-    for _ in 0..10 {
-        let _ = audio_src.write_buffer(&mut buffer1);
-        let _ = r2c.process_buffer(&buffer1, &mut buffer2);
-        let _ = fft.process_buffer(&buffer2, &mut  buffer3);
+    loop {
+        let n = match audio_src.write_buffer(&mut buffer1) {
+            Ok(n) => n,
+            Err(Error::EndOfStream) => break,
+            Err(err) => panic!("Can't read audio file: {}", err),
+        };
+        // Last frame is padded with zeros by the FFT
+        let n = r2c.process_buffer(&buffer1[..n], &mut buffer2).unwrap();
+        let _ = fft.process_buffer(&buffer2[..n], &mut  buffer3);
 
         let freq = spectrum::max_freq(&buffer3, SAMPLE_RATE);
         if freq > 1.0 {
@@ -1,5 +1,6 @@
 use dsp::core::generator::Sine;
 use dsp::core::network::UdpSink;
+use dsp::runtime::node::{SourceNode, SinkNode};
 
 
 const SAMPLE_RATE: usize = 32_000;
@@ -10,7 +11,7 @@ const FREQ: f32 = 440.;
 
 fn main() {
     let mut generator = Sine::new(FREQ, SAMPLE_RATE);
-    let mut tcp_sink = UdpSink::new(1212, "127.0.0.1:1234");
+    let mut tcp_sink = UdpSink::new(1212, "127.0.0.1:1234").unwrap();
     let mut buffer = vec![0.0; BUFFER_SIZE];
 
     loop {
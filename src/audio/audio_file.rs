@@ -2,6 +2,8 @@
 //! 
 
 use audrey::Reader;
+use crate::{Error, Result};
+use crate::runtime::node::SourceNode;
 
 
 pub struct AudioFileSource {
@@ -9,19 +11,25 @@ pub struct AudioFileSource {
 }
 
 impl AudioFileSource {
-    pub fn new(file_path: &str) -> AudioFileSource {
-        let reader = audrey::open(file_path).unwrap();
-        AudioFileSource {reader}
+    pub fn new(file_path: &str) -> Result<AudioFileSource> {
+        let reader = audrey::open(file_path).map_err(|err| match err {
+            audrey::read::ReadError::Io(err) => Error::Io(err),
+            err => Error::Format(err.to_string()),
+        })?;
+        Ok(AudioFileSource {reader})
     }
+}
 
-    pub fn write_buffer(&mut self, buffer: &mut [f32]) {
+impl SourceNode<f32> for AudioFileSource {
+    fn write_buffer(&mut self, buffer: &mut [f32]) -> Result<usize> {
         let mut samples = self.reader.samples(); 
         for i in 0..buffer.len() {
             if let Some(v) = samples.next() {
-                buffer[i] = v.unwrap();
+                buffer[i] = v.map_err(|err| Error::Format(err.to_string()))?;
             } else {
-                break;
+                return if i > 0 { Ok(i) } else { Err(Error::EndOfStream) };
             }
         }
+        Ok(buffer.len())
     }
 }
\ No newline at end of file
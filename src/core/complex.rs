@@ -1,71 +1,289 @@
 //! Node for converting between real and complex numbers
-//! 
-//! 
+//!
+//! Complex samples can be converted into a single real value (real part, magnitude, phase, ...)
+//! or into interleaved I/Q pairs, which is the usual format of the SDR recordings.
 
 use crate::num_complex::Complex32;
+use crate::{Error, Result};
+use crate::runtime::node::ProcessNode;
 
 
 /// Implement Real -> complex converter
-/// 
+///
 /// Example
-/// 
+///
 /// ```
+/// use dsp::runtime::node::ProcessNode;
 /// use dsp::core::complex::RealToComplex;
 /// use dsp::num_complex::Complex32;
-/// 
+///
 /// let mut node = RealToComplex::new();
 /// let input_buffer = vec![3.0;10];
 /// let mut output_buffer = vec![Complex32::new(0., 0.);10];
 /// node.process_buffer(&input_buffer, &mut output_buffer);
-/// 
+///
 /// assert_eq!(output_buffer[0], Complex32::new(3., 0.));
 /// assert_eq!(output_buffer[1], Complex32::new(3., 0.));
 /// ```
-
-pub struct RealToComplex {}
+///
+/// Interleaved I/Q samples, e.g. from the `FileSource` reading SDR recording
+///
+/// ```
+/// use dsp::runtime::node::ProcessNode;
+/// use dsp::core::complex::RealToComplex;
+/// use dsp::num_complex::Complex32;
+///
+/// let mut node = RealToComplex::interleaved();
+/// let mut output_buffer = vec![Complex32::new(0., 0.); 2];
+/// // Pair can be split between the buffers
+/// assert_eq!(node.process_buffer(&[1., 2., 3.], &mut output_buffer).unwrap(), 1);
+/// assert_eq!(node.process_buffer(&[4.], &mut output_buffer[1..]).unwrap(), 1);
+///
+/// assert_eq!(output_buffer, vec![Complex32::new(1., 2.), Complex32::new(3., 4.)]);
+/// ```
+pub struct RealToComplex {
+    interleaved: bool,
+    // In-phase sample waiting for the quadrature sample from the next buffer
+    pending: Option<f32>,
+}
 
 impl RealToComplex {
+    /// Real samples with zero imaginary part
     pub fn new() -> RealToComplex {
-        RealToComplex {}
+        RealToComplex { interleaved: false, pending: None }
     }
 
-    pub fn process_buffer(&mut self, input_buffer: &[f32], output_buffer: &mut [Complex32]) {
-        let n = usize::min(input_buffer.len(), output_buffer.len());
-        for i in 0..n {
-            output_buffer[i] = Complex32::new(input_buffer[i], 0.); 
+    /// Interleaved I/Q input: I0, Q0, I1, Q1, ...
+    pub fn interleaved() -> RealToComplex {
+        RealToComplex { interleaved: true, pending: None }
+    }
+
+    /// Combine separate I and Q streams.
+    /// Returns number of samples written to the output buffer.
+    ///
+    /// This is not a `ProcessNode`, because the nodes in the `Flowgraph` have only one input.
+    /// To use the separate streams in the flowgraph, interleave them before the source
+    /// and convert them with `RealToComplex::interleaved`.
+    pub fn process_iq(&mut self, i_buffer: &[f32], q_buffer: &[f32], output_buffer: &mut [Complex32]) -> Result<usize> {
+        let n = usize::min(usize::min(i_buffer.len(), q_buffer.len()), output_buffer.len());
+        for k in 0..n {
+            output_buffer[k] = Complex32::new(i_buffer[k], q_buffer[k]);
+        }
+        Ok(n)
+    }
+}
+
+impl Default for RealToComplex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessNode<f32, Complex32> for RealToComplex {
+    fn process_buffer(&mut self, input_buffer: &[f32], output_buffer: &mut [Complex32]) -> Result<usize> {
+        if !self.interleaved {
+            let n = usize::min(input_buffer.len(), output_buffer.len());
+            for i in 0..n {
+                output_buffer[i] = Complex32::new(input_buffer[i], 0.);
+            }
+            return Ok(n);
+        }
+        check_output(output_buffer.len(), self.output_size(input_buffer.len()))?;
+        let mut n = 0;
+        for v in input_buffer {
+            match self.pending.take() {
+                Some(re) => {
+                    output_buffer[n] = Complex32::new(re, *v);
+                    n += 1;
+                }
+                None => self.pending = Some(*v),
+            }
+        }
+        Ok(n)
+    }
+
+    fn output_size(&self, input_size: usize) -> usize {
+        if self.interleaved {
+            (input_size + self.pending.map_or(0, |_| 1)) / 2
+        } else {
+            input_size
         }
     }
 }
 
 
+/// Value calculated from the complex sample
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ComplexMode {
+    /// Real part
+    Real,
+    /// Imaginary part
+    Imag,
+    /// Magnitude
+    #[default]
+    Norm,
+    /// Squared magnitude (power)
+    NormSqr,
+    /// Phase in radians in range [-π, π]
+    Arg,
+    /// Power in dB: 10*log10(|x|^2)
+    Db,
+    /// Two output samples per input sample: re, im
+    Interleaved,
+}
+
+
 /// Implement Complex -> Real converter
-/// 
+///
 /// Example
-/// 
+///
 /// ```
-/// use dsp::core::complex::ComplexToReal;
+/// use dsp::runtime::node::ProcessNode;
+/// use dsp::core::complex::{ComplexToReal, ComplexMode};
 /// use dsp::num_complex::Complex32;
-/// 
+///
 /// let mut node = ComplexToReal::new();
 /// let input_buffer = vec![Complex32::new(4., 3.);10];
 /// let mut output_buffer = vec![0.0;10];
 /// node.process_buffer(&input_buffer, &mut output_buffer);
-/// 
+///
 /// assert_eq!(output_buffer[0], 5.);
 /// assert_eq!(output_buffer[1], 5.);
+///
+/// let mut node = ComplexToReal::with_mode(ComplexMode::Interleaved);
+/// let mut output_buffer = vec![0.0;4];
+/// node.process_buffer(&input_buffer[..2], &mut output_buffer);
+///
+/// assert_eq!(output_buffer, vec![4., 3., 4., 3.]);
 /// ```
-
-pub struct ComplexToReal {}
+pub struct ComplexToReal {
+    mode: ComplexMode,
+}
 
 impl ComplexToReal {
+    /// Converter which outputs magnitude
     pub fn new() -> ComplexToReal {
-        ComplexToReal {}
+        ComplexToReal { mode: ComplexMode::Norm }
+    }
+
+    /// Converter with the given mode
+    pub fn with_mode(mode: ComplexMode) -> ComplexToReal {
+        ComplexToReal { mode }
     }
 
-    pub fn process_buffer(&mut self, input_buffer: &[Complex32], output_buffer: &mut [f32]) {
+    /// Conversion mode
+    pub fn mode(&self) -> ComplexMode {
+        self.mode
+    }
+}
+
+impl Default for ComplexToReal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessNode<Complex32, f32> for ComplexToReal {
+    fn process_buffer(&mut self, input_buffer: &[Complex32], output_buffer: &mut [f32]) -> Result<usize> {
+        if self.mode == ComplexMode::Interleaved {
+            let n = usize::min(input_buffer.len(), output_buffer.len() / 2);
+            for i in 0..n {
+                output_buffer[2 * i] = input_buffer[i].re;
+                output_buffer[2 * i + 1] = input_buffer[i].im;
+            }
+            return Ok(2 * n);
+        }
         let n = usize::min(input_buffer.len(), output_buffer.len());
         for i in 0..n {
-            output_buffer[i] = input_buffer[i].norm(); 
+            let x = input_buffer[i];
+            output_buffer[i] = match self.mode {
+                ComplexMode::Real => x.re,
+                ComplexMode::Imag => x.im,
+                ComplexMode::NormSqr => x.norm_sqr(),
+                ComplexMode::Arg => x.arg(),
+                ComplexMode::Db => 10.0 * x.norm_sqr().log10(),
+                ComplexMode::Norm => x.norm(),
+                ComplexMode::Interleaved => unreachable!("Interleaved mode is processed above"),
+            };
         }
+        Ok(n)
     }
-}
\ No newline at end of file
+
+    fn output_size(&self, input_size: usize) -> usize {
+        if self.mode == ComplexMode::Interleaved { 2 * input_size } else { input_size }
+    }
+}
+
+
+fn check_output(buffer_size: usize, required: usize) -> Result<()> {
+    if buffer_size < required {
+        Err(Error::Config(format!("Output buffer has size {} but {} samples are required", buffer_size, required)))
+    } else {
+        Ok(())
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use super::*;
+
+    #[test]
+    fn test_complex_modes() {
+        let input = [Complex32::new(3., -4.), Complex32::new(0., 1.)];
+        let mut output = [0.0; 2];
+        let expected = [
+            (ComplexMode::Real, [3., 0.]),
+            (ComplexMode::Imag, [-4., 1.]),
+            (ComplexMode::Norm, [5., 1.]),
+            (ComplexMode::NormSqr, [25., 1.]),
+            (ComplexMode::Arg, [(-4f32).atan2(3.), std::f32::consts::FRAC_PI_2]),
+            (ComplexMode::Db, [25f32.log10() * 10., 0.]),
+        ];
+        for (mode, values) in expected {
+            let mut node = ComplexToReal::with_mode(mode);
+            assert_eq!(node.process_buffer(&input, &mut output).unwrap(), 2);
+            assert_approx_eq!(output[0], values[0]);
+            assert_approx_eq!(output[1], values[1]);
+        }
+    }
+
+    #[test]
+    fn test_interleaved_round_trip() {
+        let input: Vec<Complex32> = (0..5).map(|i| Complex32::new(i as f32, -(i as f32))).collect();
+        let mut c2r = ComplexToReal::with_mode(ComplexMode::Interleaved);
+        let mut r2c = RealToComplex::interleaved();
+        let mut iq = vec![0.0; c2r.output_size(5)];
+        assert_eq!(c2r.process_buffer(&input, &mut iq).unwrap(), 10);
+        // Odd buffer sizes split the pairs
+        let mut output = vec![];
+        for chunk in iq.chunks(3) {
+            let mut buffer = vec![Complex32::default(); r2c.output_size(chunk.len())];
+            let n = r2c.process_buffer(chunk, &mut buffer).unwrap();
+            output.extend_from_slice(&buffer[..n]);
+        }
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    fn test_interleaved_output_too_small() {
+        let mut r2c = RealToComplex::interleaved();
+        let mut output = [Complex32::default(); 1];
+        assert!(matches!(r2c.process_buffer(&[1., 2., 3., 4.], &mut output), Err(Error::Config(_))));
+        // Nothing was consumed
+        assert_eq!(r2c.process_buffer(&[1., 2.], &mut output).unwrap(), 1);
+        assert_eq!(output[0], Complex32::new(1., 2.));
+    }
+
+    #[test]
+    fn test_separate_iq() {
+        let mut r2c = RealToComplex::new();
+        let mut output = [Complex32::default(); 3];
+        assert_eq!(r2c.process_iq(&[1., 2., 3.], &[4., 5.], &mut output).unwrap(), 2);
+        assert_eq!(output[1], Complex32::new(2., 5.));
+    }
+}
@@ -1,6 +1,9 @@
 //! Calculate (Auto)Correlation
 //! 
 
+use crate::{Error, Result};
+use crate::runtime::node::ProcessNode;
+
 
 pub struct AutoCorrelation {
     window_size: usize,
@@ -10,13 +13,16 @@ impl AutoCorrelation {
     pub fn new(window_size: usize) -> AutoCorrelation {
         AutoCorrelation {window_size}
     }
+}
 
+impl ProcessNode<f32, f32> for AutoCorrelation {
     /// Calculate correlation between 2 buffers
     /// 
     /// Example
     /// 
     /// ```
     /// use assert_approx_eq::assert_approx_eq;
+    /// use dsp::runtime::node::{SourceNode, ProcessNode};
     /// use dsp::core::generator::Sine;
     /// use dsp::core::correlation::AutoCorrelation;
     /// 
@@ -33,7 +39,10 @@ impl AutoCorrelation {
     /// assert_approx_eq!(corr_buffer[3], 0., 1e-5f32);
     /// assert_approx_eq!(corr_buffer[4], 1.0, 1e-5f32);
     /// ```
-    pub fn process_buffer(&mut self, input_buffer: &[f32], output_buffer: &mut [f32]) {
+    fn process_buffer(&mut self, input_buffer: &[f32], output_buffer: &mut [f32]) -> Result<usize> {
+        if input_buffer.len() <= self.window_size {
+            return Err(Error::Config(format!("Input buffer must be longer then the window size {}", self.window_size)));
+        }
         let mu: f32 = input_buffer.iter().sum::<f32>() / input_buffer.len() as f32;
         let max_offset = usize::min(input_buffer.len()-self.window_size, output_buffer.len());
         // auto covariance
@@ -44,9 +53,8 @@ impl AutoCorrelation {
         }
         // normalize
         let s0 = output_buffer[0];
-        for i in 0..output_buffer.len() {
-            output_buffer[i] = output_buffer[i] / s0;
-        }
+        output_buffer[..max_offset].iter_mut().for_each(|y| *y /= s0);
+        Ok(max_offset)
     }
 }
 
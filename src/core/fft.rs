@@ -1,12 +1,23 @@
 //! Helper functions for FFT.
+//!
+//! By default transforms are not normalized, so the inverse of the forward transform is the input
+//! multiplied by the FFT size. Use `with_normalization` to scale the output, e.g. forward
+//! transform with `Normalization::None` and inverse with `Normalization::N` restores the signal.
+//!
+//! Real input has symmetric spectrum, so `RealFFT` returns only N/2+1 bins
+//! from 0 to the Nyquist frequency and `InverseRealFFT` reconstructs real signal from them.
+use std::f32::consts::PI;
 use std::sync::Arc;
 use rustfft::{Fft, FftPlanner};
 use crate::{num_complex::Complex32, window};
+use crate::{Error, Result};
+use crate::runtime::node::ProcessNode;
 
 
 pub struct ForwardFFT {
     fft: Arc<dyn Fft<f32>>,
-    window: window::Window
+    window: window::Window,
+    scale: f32,
 }
 
 pub enum WindowType {
@@ -17,35 +28,74 @@ pub enum WindowType {
     Welch,
 }
 
+/// Scaling of the FFT output
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Normalization {
+    /// No scaling
+    #[default]
+    None,
+    /// Scale by 1/N
+    N,
+    /// Scale by 1/sqrt(N)
+    SqrtN,
+    /// Preserve energy: scale by 1/sqrt(sum(w^2)), which is 1/sqrt(N) without window.
+    /// Forward and inverse unitary transforms restore the signal
+    Unitary,
+    /// Compensate coherent gain of the window: scale by 1/sum(w).
+    /// Complex tone with amplitude A has magnitude A in its bin,
+    /// real sine has A/2 in the positive and negative frequency bin.
+    Amplitude,
+}
+
+impl Normalization {
+    /// Scale factor for the transform with the given window
+    pub fn scale(&self, window: &[f32]) -> f32 {
+        let n = window.len() as f32;
+        match self {
+            Normalization::None => 1.0,
+            Normalization::N => 1.0 / n,
+            Normalization::SqrtN => 1.0 / n.sqrt(),
+            Normalization::Unitary => 1.0 / window.iter().map(|w| w * w).sum::<f32>().sqrt(),
+            Normalization::Amplitude => 1.0 / window.iter().sum::<f32>(),
+        }
+    }
+}
+
 impl ForwardFFT {
     /// Define new transformation
     /// ## Params:
     ///   * sample_size - Size of the vector which will be converter. Should be power of 2 (or 3)
     pub fn new(sample_size: usize, window_type: WindowType) -> ForwardFFT {
-        let window = match window_type {
-            WindowType::Blackman => window::blackman(sample_size),
-            WindowType::Hamming => window::hamming(sample_size),
-            WindowType::Hann => window::hann(sample_size),
-            WindowType::Welch => window::welch(sample_size),
-            _ => window::rectangular(sample_size),
-        };
+        let window = create_window(sample_size, window_type);
         let mut fft = FftPlanner::new();
-        ForwardFFT { fft: fft.plan_fft_forward(sample_size), window }
+        ForwardFFT { fft: fft.plan_fft_forward(sample_size), window, scale: 1.0 }
     }
 
+    /// Scale the output
+    pub fn with_normalization(mut self, normalization: Normalization) -> ForwardFFT {
+        self.scale = normalization.scale(self.window.as_slice());
+        self
+    }
+}
 
-    pub fn process_buffer(&mut self, input_buffer: &[Complex32], output_buffer: &mut [Complex32]) {
-        let n = usize::min(usize::min(input_buffer.len(), output_buffer.len()), self.window.len());
+impl ProcessNode<Complex32, Complex32> for ForwardFFT {
+    fn process_buffer(&mut self, input_buffer: &[Complex32], output_buffer: &mut [Complex32]) -> Result<usize> {
+        check_size(output_buffer.len(), self.fft.len())?;
+        let n = usize::min(input_buffer.len(), output_buffer.len());
         for i in 0..n {
-            output_buffer[i] = input_buffer[i].scale(self.window.as_slice()[i]); 
+            output_buffer[i] = input_buffer[i].scale(self.window.as_slice()[i] * self.scale);
         }
+        // Pad missing samples with zeros
+        output_buffer[n..].fill(Complex32::default());
         self.fft.process(output_buffer);
+        Ok(output_buffer.len())
     }
 }
 
 
 pub struct InverseFFT {
     fft: Arc<dyn Fft<f32>>,
+    scale: f32,
 }
 
 impl InverseFFT {
@@ -56,16 +106,220 @@ impl InverseFFT {
         let mut fft = FftPlanner::new();
         InverseFFT {
             fft: fft.plan_fft_inverse(sample_size),
+            scale: 1.0,
         }
     }
 
+    /// Scale the output. Window based normalizations use rectangular window
+    pub fn with_normalization(mut self, normalization: Normalization) -> InverseFFT {
+        self.scale = normalization.scale(&vec![1.0; self.fft.len()]);
+        self
+    }
+}
 
-    pub fn process_buffer(&mut self, input_buffer: &[Complex32], output_buffer: &mut [Complex32]) {
+impl ProcessNode<Complex32, Complex32> for InverseFFT {
+    fn process_buffer(&mut self, input_buffer: &[Complex32], output_buffer: &mut [Complex32]) -> Result<usize> {
+        check_size(output_buffer.len(), self.fft.len())?;
         let n = usize::min(input_buffer.len(), output_buffer.len());
         for i in 0..n {
-            output_buffer[i] = input_buffer[i]; 
+            output_buffer[i] = input_buffer[i].scale(self.scale);
         }
+        output_buffer[n..].fill(Complex32::default());
         self.fft.process(output_buffer);
+        Ok(output_buffer.len())
+    }
+}
+
+/// Forward FFT of the real signal.
+/// Output has N/2+1 bins from 0 to the Nyquist frequency.
+///
+/// Example
+///
+/// ```
+/// use dsp::runtime::node::ProcessNode;
+/// use dsp::core::fft::{RealFFT, WindowType};
+/// use dsp::num_complex::Complex32;
+///
+/// let mut fft = RealFFT::new(8, WindowType::Rectangular);
+/// let input: Vec<f32> = (0..8).map(|i| if i % 2 == 0 { 1.0 } else { -1.0 }).collect();
+/// let mut output = vec![Complex32::new(0., 0.); fft.output_size(8)];
+/// assert_eq!(fft.process_buffer(&input, &mut output).unwrap(), 5);
+/// assert!((output[4] - Complex32::new(8., 0.)).norm() < 1e-5);
+/// ```
+pub struct RealFFT {
+    size: usize,
+    // FFT of size N/2 for the even sizes or N for the odd sizes
+    fft: Arc<dyn Fft<f32>>,
+    window: window::Window,
+    scale: f32,
+    twiddles: Vec<Complex32>,
+    buffer: Vec<Complex32>,
+}
+
+impl RealFFT {
+    /// Define new transformation
+    /// ## Params:
+    ///   * sample_size - Size of the real signal N. Even sizes are 2 times faster
+    pub fn new(sample_size: usize, window_type: WindowType) -> RealFFT {
+        let mut planner = FftPlanner::new();
+        let fft_size = if sample_size % 2 == 0 { sample_size / 2 } else { sample_size };
+        RealFFT {
+            size: sample_size,
+            fft: planner.plan_fft_forward(fft_size),
+            window: create_window(sample_size, window_type),
+            scale: 1.0,
+            twiddles: twiddles(sample_size),
+            buffer: vec![Complex32::default(); fft_size],
+        }
+    }
+
+    /// Scale the output
+    pub fn with_normalization(mut self, normalization: Normalization) -> RealFFT {
+        self.scale = normalization.scale(self.window.as_slice());
+        self
+    }
+
+    /// Size of the transformed signal
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    /// True if the transform size is 0
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+}
+
+impl ProcessNode<f32, Complex32> for RealFFT {
+    fn process_buffer(&mut self, input_buffer: &[f32], output_buffer: &mut [Complex32]) -> Result<usize> {
+        let bins = self.size / 2 + 1;
+        check_size(output_buffer.len(), bins)?;
+        let n = usize::min(input_buffer.len(), self.size);
+        // Missing samples are zeros
+        let sample = |i: usize| if i < n { input_buffer[i] * self.window.as_slice()[i] * self.scale } else { 0.0 };
+        if self.size % 2 != 0 {
+            for (i, b) in self.buffer.iter_mut().enumerate() {
+                *b = Complex32::new(sample(i), 0.);
+            }
+            self.fft.process(&mut self.buffer);
+            output_buffer.copy_from_slice(&self.buffer[..bins]);
+            return Ok(bins);
+        }
+        // Pack even samples into real part and odd samples into imaginary part
+        let m = self.buffer.len();
+        for (i, b) in self.buffer.iter_mut().enumerate() {
+            *b = Complex32::new(sample(2 * i), sample(2 * i + 1));
+        }
+        self.fft.process(&mut self.buffer);
+        for (k, y) in output_buffer[..bins].iter_mut().enumerate() {
+            let z = self.buffer[k % m];
+            let z_conj = self.buffer[(m - k % m) % m].conj();
+            let even = (z + z_conj) * 0.5;
+            let odd = (z - z_conj) * Complex32::new(0., -0.5);
+            *y = even + self.twiddles[k] * odd;
+        }
+        Ok(bins)
+    }
+
+    fn output_size(&self, _input_size: usize) -> usize {
+        self.size / 2 + 1
+    }
+}
+
+
+/// Inverse FFT which returns real signal.
+/// Input has N/2+1 bins from 0 to the Nyquist frequency, missing bins are zeros.
+pub struct InverseRealFFT {
+    size: usize,
+    fft: Arc<dyn Fft<f32>>,
+    scale: f32,
+    twiddles: Vec<Complex32>,
+    buffer: Vec<Complex32>,
+}
+
+impl InverseRealFFT {
+    /// Define new transformation
+    /// ## Params:
+    ///   * sample_size - Size of the real signal N. Even sizes are 2 times faster
+    pub fn new(sample_size: usize) -> InverseRealFFT {
+        let mut planner = FftPlanner::new();
+        let fft_size = if sample_size % 2 == 0 { sample_size / 2 } else { sample_size };
+        InverseRealFFT {
+            size: sample_size,
+            fft: planner.plan_fft_inverse(fft_size),
+            scale: 1.0,
+            twiddles: twiddles(sample_size),
+            buffer: vec![Complex32::default(); fft_size],
+        }
+    }
+
+    /// Scale the output. Window based normalizations use rectangular window
+    pub fn with_normalization(mut self, normalization: Normalization) -> InverseRealFFT {
+        self.scale = normalization.scale(&vec![1.0; self.size]);
+        self
+    }
+}
+
+impl ProcessNode<Complex32, f32> for InverseRealFFT {
+    fn process_buffer(&mut self, input_buffer: &[Complex32], output_buffer: &mut [f32]) -> Result<usize> {
+        check_size(output_buffer.len(), self.size)?;
+        let bins = self.size / 2 + 1;
+        let bin = |k: usize| if k < usize::min(input_buffer.len(), bins) { input_buffer[k] } else { Complex32::default() };
+        if self.size % 2 != 0 {
+            // Restore the negative frequencies
+            for (k, b) in self.buffer.iter_mut().enumerate() {
+                *b = if k < bins { bin(k) } else { bin(self.size - k).conj() };
+            }
+            self.fft.process(&mut self.buffer);
+            for (y, b) in output_buffer.iter_mut().zip(&self.buffer) {
+                *y = b.re * self.scale;
+            }
+            return Ok(self.size);
+        }
+        let m = self.buffer.len();
+        for k in 0..m {
+            let x = bin(k);
+            let x_conj = bin(m - k).conj();
+            let even = x + x_conj;
+            let odd = (x - x_conj) * self.twiddles[k].conj();
+            self.buffer[k] = even + odd * Complex32::new(0., 1.);
+        }
+        self.fft.process(&mut self.buffer);
+        for (i, b) in self.buffer.iter().enumerate() {
+            output_buffer[2 * i] = b.re * self.scale;
+            output_buffer[2 * i + 1] = b.im * self.scale;
+        }
+        Ok(self.size)
+    }
+
+    fn output_size(&self, _input_size: usize) -> usize {
+        self.size
+    }
+}
+
+fn create_window(sample_size: usize, window_type: WindowType) -> window::Window {
+    match window_type {
+        WindowType::Blackman => window::blackman(sample_size),
+        WindowType::Hamming => window::hamming(sample_size),
+        WindowType::Hann => window::hann(sample_size),
+        WindowType::Welch => window::welch(sample_size),
+        _ => window::rectangular(sample_size),
+    }
+}
+
+/// exp(-2πjk/N) for k in 0..=N/2
+fn twiddles(size: usize) -> Vec<Complex32> {
+    (0..=size / 2)
+        .map(|k| Complex32::from_polar(1.0, -2.0 * PI * k as f32 / size as f32))
+        .collect()
+}
+
+/// FFT can only be calculated on the buffer of the planned size
+fn check_size(buffer_size: usize, fft_size: usize) -> Result<()> {
+    if buffer_size != fft_size {
+        Err(Error::Config(format!("Output buffer has size {} but FFT size is {}", buffer_size, fft_size)))
+    } else {
+        Ok(())
     }
 }
 
@@ -90,4 +344,104 @@ mod tests {
         let expected = vec![Complex32::new(1., 0.); 4];
         assert_eq!(&output_buffer, &expected);
     }
+
+    #[test]
+    fn test_fft_zero_padding() {
+        let input_buffer = vec![Complex32::new(1., 0.)];
+        let mut output_buffer = vec![Complex32::new(3., 0.); 4];
+        
+        let mut ft = ForwardFFT::new(4, WindowType::Rectangular);
+        let n = ft.process_buffer(&input_buffer, &mut output_buffer).unwrap();
+        let expected = vec![Complex32::new(1., 0.); 4];
+        assert_eq!(n, 4);
+        assert_eq!(&output_buffer, &expected);
+    }
+
+    #[test]
+    fn test_real_fft_matches_complex() {
+        for n in [16, 15, 2] {
+            let input: Vec<f32> = (0..n).map(|i| ((i * 7 % 5) as f32) - 1.5).collect();
+            let complex: Vec<Complex32> = input.iter().map(|x| Complex32::new(*x, 0.)).collect();
+            let mut expected = vec![Complex32::default(); n];
+            ForwardFFT::new(n, WindowType::Hann).process_buffer(&complex, &mut expected).unwrap();
+
+            let mut fft = RealFFT::new(n, WindowType::Hann);
+            let mut output = vec![Complex32::default(); fft.output_size(n)];
+            assert_eq!(fft.process_buffer(&input, &mut output).unwrap(), n / 2 + 1);
+            for (y, e) in output.iter().zip(&expected) {
+                assert!((y - e).norm() < 1e-4, "Wrong bin for size {}: {} != {}", n, y, e);
+            }
+        }
+    }
+
+    #[test]
+    fn test_real_fft_round_trip() {
+        for n in [16, 15] {
+            let input: Vec<f32> = (0..n).map(|i| ((i * 7 % 5) as f32) - 1.5).collect();
+            let mut spectrum = vec![Complex32::default(); n / 2 + 1];
+            let mut output = vec![0.0; n];
+            RealFFT::new(n, WindowType::Rectangular).process_buffer(&input, &mut spectrum).unwrap();
+            let mut ifft = InverseRealFFT::new(n);
+            assert_eq!(ifft.process_buffer(&spectrum, &mut output).unwrap(), n);
+            for (y, x) in output.iter().zip(&input) {
+                assert!((y / n as f32 - x).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_normalization() {
+        // Complex tone with amplitude 2 in bin 3
+        let n = 32;
+        let input: Vec<Complex32> = (0..n)
+            .map(|i| Complex32::from_polar(2.0, 2.0 * PI * 3.0 * i as f32 / n as f32))
+            .collect();
+        let mut output = vec![Complex32::default(); n];
+        for window in [WindowType::Rectangular, WindowType::Hann, WindowType::Blackman] {
+            let mut fft = ForwardFFT::new(n, window).with_normalization(Normalization::Amplitude);
+            fft.process_buffer(&input, &mut output).unwrap();
+            assert!((output[3].norm() - 2.0).abs() < 1e-5);
+        }
+        let energy = |xs: &[Complex32]| xs.iter().map(|x| x.norm_sqr()).sum::<f32>();
+        let mut fft = ForwardFFT::new(n, WindowType::Rectangular).with_normalization(Normalization::Unitary);
+        fft.process_buffer(&input, &mut output).unwrap();
+        assert!((energy(&output) - energy(&input)).abs() < 1e-3);
+        let mut fft = ForwardFFT::new(n, WindowType::Rectangular).with_normalization(Normalization::SqrtN);
+        fft.process_buffer(&input, &mut output).unwrap();
+        assert!((output[3].norm() - 2.0 * (n as f32).sqrt()).abs() < 1e-4);
+
+        // Inverse of the unitary transform restores the signal
+        let mut restored = vec![Complex32::default(); n];
+        let mut ifft = InverseFFT::new(n).with_normalization(Normalization::Unitary);
+        ifft.process_buffer(&output, &mut restored).unwrap();
+        for (y, x) in restored.iter().zip(&input) {
+            assert!((y - x).norm() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_real_fft_normalization() {
+        let n = 16;
+        let input: Vec<f32> = (0..n).map(|i| ((i * 7 % 5) as f32) - 1.5).collect();
+        let mut spectrum = vec![Complex32::default(); n / 2 + 1];
+        let mut output = vec![0.0; n];
+        RealFFT::new(n, WindowType::Rectangular).process_buffer(&input, &mut spectrum).unwrap();
+        InverseRealFFT::new(n).with_normalization(Normalization::N).process_buffer(&spectrum, &mut output).unwrap();
+        for (y, x) in output.iter().zip(&input) {
+            assert!((y - x).abs() < 1e-5);
+        }
+        let mut fft = RealFFT::new(n, WindowType::Hann).with_normalization(Normalization::Amplitude);
+        fft.process_buffer(&[3.0; 16], &mut spectrum).unwrap();
+        assert!((spectrum[0].re - 3.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_wrong_buffer_size() {
+        let input_buffer = vec![Complex32::new(1., 0.); 4];
+        let mut output_buffer = vec![Complex32::new(0., 0.); 3];
+        
+        let mut ft = ForwardFFT::new(4, WindowType::Rectangular);
+        let result = ft.process_buffer(&input_buffer, &mut output_buffer);
+        assert!(matches!(result, Err(Error::Config(_))));
+    }
 }
@@ -1,8 +1,11 @@
 //! Nodes for saving and reading data to/from file
 //! 
 use std::fs::File;
+use std::io::ErrorKind;
 use byteorder::{ReadBytesExt, WriteBytesExt}; 
 use byteorder::LittleEndian;
+use crate::{Error, Result};
+use crate::runtime::node::{SinkNode, SourceNode};
 
 
 /// Save binary data into a file
@@ -10,54 +13,97 @@ use byteorder::LittleEndian;
 /// Example
 /// 
 /// ```
+/// use dsp::runtime::node::SinkNode;
 /// use dsp::core::file::FileSink;
 /// 
-/// let mut node = FileSink::new("target/file.dat");
+/// let mut node = FileSink::new("target/file.dat").unwrap();
 /// let input_buffer = vec![1.;100];
-/// node.read_buffer(&input_buffer);
+/// node.read_buffer(&input_buffer).unwrap();
 /// ```
 pub struct FileSink {
-    file: Option<File>,
+    file: File,
 }
 
 impl FileSink {
-    pub fn new(file_name: &str) -> FileSink {
-        if let Ok(file) = File::create(file_name) {
-            FileSink {file: Some(file)}
-        } else {
-            FileSink{file: None}
-        }
+    pub fn new(file_name: &str) -> Result<FileSink> {
+        let file = File::create(file_name)?;
+        Ok(FileSink {file})
     }
+}
 
-    pub fn read_buffer(&mut self, input_buffer: &[f32]) {
-        let mut file = self.file.as_ref().unwrap();
+impl SinkNode<f32> for FileSink {
+    fn read_buffer(&mut self, input_buffer: &[f32]) -> Result<()> {
         for v in input_buffer {
-            file.write_f32::<LittleEndian>(*v).expect("Can't reead from file");
+            self.file.write_f32::<LittleEndian>(*v)?;
         }
-        let _ = file.sync_all();
+        self.file.sync_all()?;
+        Ok(())
     }
 }
 
 
 /// Load binary data from a file
 /// 
+/// Example
+/// 
+/// ```
+/// use dsp::Error;
+/// use dsp::runtime::node::{SinkNode, SourceNode};
+/// use dsp::core::file::{FileSink, FileSource};
+/// 
+/// let path = std::env::temp_dir().join("source.dat");
+/// let mut sink = FileSink::new(path.to_str().unwrap()).unwrap();
+/// sink.read_buffer(&vec![1.; 10]).unwrap();
+/// 
+/// let mut node = FileSource::new(path.to_str().unwrap()).unwrap();
+/// let mut buffer = vec![0.; 8];
+/// assert_eq!(node.write_buffer(&mut buffer).unwrap(), 8);
+/// assert_eq!(node.write_buffer(&mut buffer).unwrap(), 2);
+/// assert!(matches!(node.write_buffer(&mut buffer), Err(Error::EndOfStream)));
+/// ```
 pub struct FileSource {
     file: File,
 }
 
 impl FileSource {
-    pub fn new(file_name: &str) -> FileSource {
-        let file = File::open(file_name).expect("Can't open file");
-        FileSource {file}
+    pub fn new(file_name: &str) -> Result<FileSource> {
+        let file = File::open(file_name)?;
+        Ok(FileSource {file})
     }
+}
 
-    pub fn write_buffer(&mut self, output_buffer: &mut [f32]) {
-        for i in 0..output_buffer.len() {
-            if let Ok(v) = self.file.read_f32::<LittleEndian>() {
-                output_buffer[i] = v;
-            } else {
-                break
+impl SourceNode<f32> for FileSource {
+    fn write_buffer(&mut self, output_buffer: &mut [f32]) -> Result<usize> {
+        for (i, y) in output_buffer.iter_mut().enumerate() {
+            match self.file.read_f32::<LittleEndian>() {
+                Ok(v) => *y = v,
+                Err(err) if err.kind() == ErrorKind::UnexpectedEof => {
+                    return if i > 0 { Ok(i) } else { Err(Error::EndOfStream) }
+                },
+                Err(err) => return Err(err.into()),
             }
         }
+        Ok(output_buffer.len())
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_file() {
+        let result = FileSource::new("target/missing/file.dat");
+        assert!(matches!(result, Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn test_sink_wrong_path() {
+        let result = FileSink::new("target/missing/file.dat");
+        assert!(matches!(result, Err(Error::Io(_))));
     }
 }
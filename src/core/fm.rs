@@ -2,6 +2,8 @@
 //! 
 
 use num_complex::Complex32;
+use crate::Result;
+use crate::runtime::node::ProcessNode;
 
 
 /// Demodulation block using the conjugate delay method
@@ -10,6 +12,7 @@ use num_complex::Complex32;
 /// Example
 /// 
 /// ```
+/// use dsp::runtime::node::ProcessNode;
 /// use dsp::num_complex::Complex32;
 /// use dsp::core::fm::QuadratureDetector;
 /// 
@@ -27,18 +30,25 @@ impl QuadratureDetector {
         Self {last_sample: Complex32::default()}
     }
 
-    pub fn process_buffer(&mut self, input_buffer: &[Complex32], output_buffer: &mut [f32]) {
-        let n = usize::min(input_buffer.len(), output_buffer.len());
-        for i in 0..n {
-            let v = &input_buffer[i];
-            output_buffer[i] = (v * self.last_sample.conj()).arg(); // Obtain phase of x[n] * conj(x[n-1])
-            self.last_sample = *v;
-        }
-    }
-
     pub fn process_sample(&mut self, v: &Complex32) -> f32 {
         let d = (v * self.last_sample.conj()).arg(); // Obtain phase of x[n] * conj(x[n-1])
         self.last_sample = *v;
         d
     }
 }
+
+impl Default for QuadratureDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessNode<Complex32, f32> for QuadratureDetector {
+    fn process_buffer(&mut self, input_buffer: &[Complex32], output_buffer: &mut [f32]) -> Result<usize> {
+        let n = usize::min(input_buffer.len(), output_buffer.len());
+        for i in 0..n {
+            output_buffer[i] = self.process_sample(&input_buffer[i]);
+        }
+        Ok(n)
+    }
+}
@@ -3,42 +3,123 @@
 
 use num_complex::Complex32;
 
-use super::generator::Sine;
+use crate::Result;
+use crate::runtime::node::ProcessNode;
+use super::generator::ComplexSine;
 
 
-/// Multiply buffer sample by constant value
+/// Shift spectrum of the complex signal by the given frequency. 
+/// Signal is multiplied by the complex exponential e^{jωn}, so only the frequency
+/// translated copy of the signal is produced.
 /// 
 /// Example
 /// 
 /// ```
+/// use assert_approx_eq::assert_approx_eq;
+/// use dsp::runtime::node::{SourceNode, ProcessNode};
 /// use dsp::num_complex::Complex32;
-/// use dsp::core::generator::Sine;
+/// use dsp::core::generator::ComplexSine;
 /// use dsp::core::freq_shift::FrequencyShift;
 /// 
-/// let mut node = FrequencyShift::new(30., 1024);
-/// let input_buffer = vec![Complex32::default(); 1024];
+/// // Move 100Hz signal down to 40Hz
+/// let mut source = ComplexSine::new(100., 1024);
+/// let mut node = FrequencyShift::new(-60., 1024);
+/// let mut input_buffer = vec![Complex32::default(); 1024];
 /// let mut output_buffer = vec![Complex32::default(); 1024];
-/// let source = Sine::new(10., 1024);
-/// node.process_buffer(&input_buffer, &mut output_buffer);
+/// source.write_buffer(&mut input_buffer).unwrap();
+/// node.process_buffer(&input_buffer, &mut output_buffer).unwrap();
+/// 
+/// let mut expected = vec![Complex32::default(); 1024];
+/// ComplexSine::new(40., 1024).write_buffer(&mut expected).unwrap();
+/// for (v, e) in output_buffer.iter().zip(expected) {
+///     assert_approx_eq!(v.re, e.re, 1e-3f32);
+///     assert_approx_eq!(v.im, e.im, 1e-3f32);
+/// }
 /// ```
 pub struct FrequencyShift {
-    offset_signal: Sine,
+    offset_signal: ComplexSine,
 }
 
 impl FrequencyShift {
+    /// Create new frequency shifter
+    ///   * freq_offset - Frequency in Hz added to the signal. Can be negative
+    ///   * sample_rate - Number of samples/s
     pub fn new(freq_offset: f32, sample_rate: usize) -> Self {
-        let offset_signal = Sine::new(freq_offset as f32, sample_rate);
+        let offset_signal = ComplexSine::new(freq_offset, sample_rate);
         Self {offset_signal}
     }
 
-    pub fn process_buffer(&mut self, input_buffer: &[Complex32], output_buffer: &mut [Complex32]) {
+    /// Change frequency offset without phase discontinuity
+    pub fn set_offset(&mut self, freq_offset: f32) {
+        self.offset_signal.set_frequency(freq_offset);
+    }
+
+    pub fn process_sample(&mut self, v: &Complex32) -> Complex32 {
+       self.offset_signal.next().unwrap() * v 
+    }
+}
+
+impl ProcessNode<Complex32, Complex32> for FrequencyShift {
+    fn process_buffer(&mut self, input_buffer: &[Complex32], output_buffer: &mut [Complex32]) -> Result<usize> {
         let n = usize::min(input_buffer.len(), output_buffer.len());
         for i in 0..n {
             output_buffer[i] = self.offset_signal.next().unwrap() * input_buffer[i]; 
         }
+        Ok(n)
     }
+}
 
-    pub fn process_sample(&mut self, v: &Complex32) -> Complex32 {
-       self.offset_signal.next().unwrap() * v 
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::fft::{ForwardFFT, WindowType};
+    use crate::runtime::node::SourceNode;
+    use crate::vector;
+
+    fn magnitudes(buffer: &[Complex32]) -> Vec<f32> {
+        let mut fft = ForwardFFT::new(buffer.len(), WindowType::Rectangular);
+        let mut spectrum = vec![Complex32::default(); buffer.len()];
+        fft.process_buffer(buffer, &mut spectrum).unwrap();
+        spectrum.iter().map(|v| v.norm()).collect()
+    }
+
+    fn peak_bin(buffer: &[Complex32]) -> usize {
+        vector::argmax(&magnitudes(buffer))
+    }
+
+    #[test]
+    fn test_no_image() {
+        // Real mixer would produce both 40 and 160 bins
+        let mut source = ComplexSine::new(100., 256);
+        let mut node = FrequencyShift::new(60., 256);
+        let mut input_buffer = vec![Complex32::default(); 256];
+        let mut output_buffer = vec![Complex32::default(); 256];
+        source.write_buffer(&mut input_buffer).unwrap();
+        node.process_buffer(&input_buffer, &mut output_buffer).unwrap();
+
+        let spectrum = magnitudes(&output_buffer);
+        assert_eq!(vector::argmax(&spectrum), 160);
+        assert!(spectrum[40] / spectrum[160] < 1e-3);
+    }
+
+    #[test]
+    fn test_negative_offset_and_retune() {
+        let mut source = ComplexSine::new(10., 256);
+        let mut node = FrequencyShift::new(-30., 256);
+        let mut input_buffer = vec![Complex32::default(); 256];
+        let mut output_buffer = vec![Complex32::default(); 256];
+        source.write_buffer(&mut input_buffer).unwrap();
+        node.process_buffer(&input_buffer, &mut output_buffer).unwrap();
+        // -20Hz is in the upper half of the spectrum
+        assert_eq!(peak_bin(&output_buffer), 236);
+
+        node.set_offset(50.);
+        source.write_buffer(&mut input_buffer).unwrap();
+        node.process_buffer(&input_buffer, &mut output_buffer).unwrap();
+        assert_eq!(peak_bin(&output_buffer), 60);
     }
 }
@@ -6,6 +6,12 @@
 
 use std::f32;
 use std::f32::consts::PI;
+use num_complex::Complex32;
+use crate::Result;
+#[cfg(feature = "random")]
+use crate::Error;
+use crate::runtime::node::SourceNode;
+use super::nco::Nco;
 #[cfg(feature = "random")]
 use rand;
 #[cfg(feature = "random")]
@@ -19,6 +25,7 @@ use rand_distr::{Normal, Distribution};
 /// Example
 /// 
 /// ```
+/// use dsp::runtime::node::SourceNode;
 /// use dsp::core::generator::Impulse;
 /// 
 /// let mut signal = Impulse::new();
@@ -37,9 +44,11 @@ impl Impulse {
     pub fn new() -> Impulse {
         Impulse {impulse_send: false}
     }
+}
 
-    pub fn write_buffer(&mut self, buffer: &mut [f32]) {
-        for e in buffer.iter_mut() {*e = self.next().unwrap()};
+impl Default for Impulse {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -58,6 +67,13 @@ impl Iterator for Impulse {
     }
 }
 
+impl SourceNode<f32> for Impulse {
+    fn write_buffer(&mut self, buffer: &mut [f32]) -> Result<usize> {
+        for e in buffer.iter_mut() {*e = self.next().unwrap()};
+        Ok(buffer.len())
+    }
+}
+
 
 /// Step signal
 /// x[n] = 1 if n > step_pos
@@ -66,6 +82,7 @@ impl Iterator for Impulse {
 /// Example
 /// 
 /// ```
+/// use dsp::runtime::node::SourceNode;
 /// use dsp::core::generator::Step;
 /// 
 /// let mut signal = Step::new(2);
@@ -85,10 +102,6 @@ impl Step {
     pub fn new(step_pos: usize) -> Step {
         Step{ step_pos }
     }
-    
-    pub fn write_buffer(&mut self, buffer: &mut [f32]) {
-        for e in buffer.iter_mut() {*e = self.next().unwrap()};
-    }
 }
 
 // Iterator implementation
@@ -106,6 +119,13 @@ impl Iterator for Step {
     }
 }
 
+impl SourceNode<f32> for Step {
+    fn write_buffer(&mut self, buffer: &mut [f32]) -> Result<usize> {
+        for e in buffer.iter_mut() {*e = self.next().unwrap()};
+        Ok(buffer.len())
+    }
+}
+
 
 /// Sinusoidal signal
 /// 
@@ -113,6 +133,7 @@ impl Iterator for Step {
 /// 
 /// ```
 /// use assert_approx_eq::assert_approx_eq;
+/// use dsp::runtime::node::SourceNode;
 /// use dsp::core::generator::Sine;
 /// 
 /// let mut signal = Sine::new(2.0, 8);
@@ -125,9 +146,7 @@ impl Iterator for Step {
 /// assert_approx_eq!(buffer[3], -1.0, 1e-5f32);
 /// ```
 pub struct Sine {
-    step_pos: usize,
-    freq: f32,
-    sample_rate: usize,
+    nco: Nco,
 }
 
 impl Sine {
@@ -135,11 +154,22 @@ impl Sine {
     ///   * freq - signal frequency
     ///   * sample_rate - Number of samples/s
     pub fn new(freq: f32, sample_rate: usize) -> Sine {
-        Sine { step_pos: 0, freq, sample_rate}
+        Sine { nco: Nco::new(freq, sample_rate) }
     }
-    
-    pub fn write_buffer(&mut self, buffer: &mut [f32]) {
-        for e in buffer.iter_mut() {*e = self.next().unwrap()};
+
+    /// Create sinusoid generator which uses lookup table with 2^lut_bits samples
+    pub fn with_lut(freq: f32, sample_rate: usize, lut_bits: u32) -> Result<Sine> {
+        Ok(Sine { nco: Nco::with_lut(freq, sample_rate, lut_bits)? })
+    }
+
+    /// Change frequency without phase discontinuity
+    pub fn set_frequency(&mut self, freq: f32) {
+        self.nco.set_frequency(freq);
+    }
+
+    /// Set phase of the next sample in radians
+    pub fn set_phase(&mut self, phase: f32) {
+        self.nco.set_phase(phase);
     }
 }
 
@@ -148,22 +178,93 @@ impl Iterator for Sine {
     type Item = f32;
     
     fn next(&mut self) -> Option<Self::Item> {
-        let w = 2.0 * PI * self.freq / (self.sample_rate as f32);
-        let sample = f32::sin((self.step_pos as f32) * w);
-        self.step_pos += 1;
-        if self.step_pos >= self.sample_rate {
-            self.step_pos = 0;
-        }
+        let sample = self.nco.sin();
+        self.nco.step();
+        Some(sample)
+    }
+}
+
+impl SourceNode<f32> for Sine {
+    fn write_buffer(&mut self, buffer: &mut [f32]) -> Result<usize> {
+        for e in buffer.iter_mut() {*e = self.next().unwrap()};
+        Ok(buffer.len())
+    }
+}
+
+/// Complex exponential signal x[n] = e^{jωn}
+/// 
+/// Negative frequency gives the signal which rotates clockwise.
+/// 
+/// Example
+/// 
+/// ```
+/// use assert_approx_eq::assert_approx_eq;
+/// use dsp::runtime::node::SourceNode;
+/// use dsp::num_complex::Complex32;
+/// use dsp::core::generator::ComplexSine;
+/// 
+/// let mut signal = ComplexSine::new(2.0, 8);
+/// let mut buffer = vec![Complex32::default();4];
+/// let _ = signal.write_buffer(&mut buffer);
+/// 
+/// assert_approx_eq!(buffer[1].re, 0.0, 1e-5f32);
+/// assert_approx_eq!(buffer[1].im, 1.0, 1e-5f32);
+/// assert_approx_eq!(buffer[2].re, -1.0, 1e-5f32);
+/// assert_approx_eq!(buffer[2].im, 0.0, 1e-5f32);
+/// ```
+pub struct ComplexSine {
+    nco: Nco,
+}
+
+impl ComplexSine {
+    /// Create new complex exponential generator
+    ///   * freq - signal frequency. Can be negative
+    ///   * sample_rate - Number of samples/s
+    pub fn new(freq: f32, sample_rate: usize) -> ComplexSine {
+        ComplexSine { nco: Nco::new(freq, sample_rate) }
+    }
+
+    /// Create complex exponential generator which uses lookup table with 2^lut_bits samples
+    pub fn with_lut(freq: f32, sample_rate: usize, lut_bits: u32) -> Result<ComplexSine> {
+        Ok(ComplexSine { nco: Nco::with_lut(freq, sample_rate, lut_bits)? })
+    }
+
+    /// Change frequency without phase discontinuity
+    pub fn set_frequency(&mut self, freq: f32) {
+        self.nco.set_frequency(freq);
+    }
+
+    /// Set phase of the next sample in radians
+    pub fn set_phase(&mut self, phase: f32) {
+        self.nco.set_phase(phase);
+    }
+}
+
+// Iterator implementation
+impl Iterator for ComplexSine {
+    type Item = Complex32;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = Complex32::new(self.nco.cos(), self.nco.sin());
+        self.nco.step();
         Some(sample)
     }
 }
 
+impl SourceNode<Complex32> for ComplexSine {
+    fn write_buffer(&mut self, buffer: &mut [Complex32]) -> Result<usize> {
+        for e in buffer.iter_mut() {*e = self.next().unwrap()};
+        Ok(buffer.len())
+    }
+}
+
 /// Generate triangular signal
 /// 
 /// Example
 /// 
 /// ```
 /// use assert_approx_eq::assert_approx_eq;
+/// use dsp::runtime::node::SourceNode;
 /// use dsp::core::generator::Sawtooth;
 /// 
 /// let mut signal = Sawtooth::new(4.0, 16);
@@ -177,9 +278,7 @@ impl Iterator for Sine {
 /// assert_approx_eq!(buffer[4], -1.0, 1e-5f32);
 /// ```
 pub struct Sawtooth {
-    step_pos: usize,
-    freq: f32,
-    sample_rate: usize,
+    nco: Nco,
 }
 
 impl Sawtooth {
@@ -187,11 +286,17 @@ impl Sawtooth {
     ///   * freq - signal frequency
     ///   * sample_rate - Number of samples/s
     pub fn new(freq: f32, sample_rate: usize) -> Sawtooth {
-        Sawtooth { step_pos: 0, freq, sample_rate}
+        Sawtooth { nco: Nco::new(freq, sample_rate) }
     }
-    
-    pub fn write_buffer(&mut self, buffer: &mut [f32]) {
-        for e in buffer.iter_mut() {*e = self.next().unwrap()};
+
+    /// Change frequency without phase discontinuity
+    pub fn set_frequency(&mut self, freq: f32) {
+        self.nco.set_frequency(freq);
+    }
+
+    /// Set phase of the next sample in radians
+    pub fn set_phase(&mut self, phase: f32) {
+        self.nco.set_phase(phase);
     }
 }
 
@@ -200,15 +305,19 @@ impl Iterator for Sawtooth {
     type Item = f32;
     
     fn next(&mut self) -> Option<Self::Item> {
-        let sample = 2.0 * ((self.step_pos as f32) * self.freq / (self.sample_rate as f32)).fract() - 1.0;
-        self.step_pos += 1;
-        if self.step_pos >= self.sample_rate {
-            self.step_pos = 0;
-        }
+        let sample = (2.0 * self.nco.cycle_pos() - 1.0) as f32;
+        self.nco.step();
         Some(sample)
     }
 }
 
+impl SourceNode<f32> for Sawtooth {
+    fn write_buffer(&mut self, buffer: &mut [f32]) -> Result<usize> {
+        for e in buffer.iter_mut() {*e = self.next().unwrap()};
+        Ok(buffer.len())
+    }
+}
+
 
 /// Generate square signal
 /// 
@@ -216,6 +325,7 @@ impl Iterator for Sawtooth {
 /// 
 /// ```
 /// use assert_approx_eq::assert_approx_eq;
+/// use dsp::runtime::node::SourceNode;
 /// use dsp::core::generator::Square;
 /// 
 /// let mut signal = Square::new(4.0, 16);
@@ -229,9 +339,7 @@ impl Iterator for Sawtooth {
 /// assert_approx_eq!(buffer[4], 1.0, 1e-5f32);
 /// ```
 pub struct Square {
-    step_pos: usize,
-    freq: f32,
-    sample_rate: usize,
+    nco: Nco,
 }
 
 impl Square {
@@ -239,11 +347,17 @@ impl Square {
     ///   * freq - signal frequency
     ///   * sample_rate - Number of samples/s
     pub fn new(freq: f32, sample_rate: usize) -> Square {
-        Square { step_pos: 0, freq, sample_rate}
+        Square { nco: Nco::new(freq, sample_rate) }
     }
-    
-    pub fn write_buffer(&mut self, buffer: &mut [f32]) {
-        for e in buffer.iter_mut() {*e = self.next().unwrap()};
+
+    /// Change frequency without phase discontinuity
+    pub fn set_frequency(&mut self, freq: f32) {
+        self.nco.set_frequency(freq);
+    }
+
+    /// Set phase of the next sample in radians
+    pub fn set_phase(&mut self, phase: f32) {
+        self.nco.set_phase(phase);
     }
 }
 
@@ -252,19 +366,23 @@ impl Iterator for Square {
     type Item = f32;
     
     fn next(&mut self) -> Option<Self::Item> {
-        let sample = if ((self.step_pos as f32) * self.freq/(self.sample_rate as f32)).fract() < 0.5 {
+        let sample = if self.nco.cycle_pos() < 0.5 {
             1.0
         } else {
             -1.0
         };
-        self.step_pos += 1;
-        if self.step_pos >= self.sample_rate {
-            self.step_pos = 0;
-        }
+        self.nco.step();
         Some(sample)
     }
 }
 
+impl SourceNode<f32> for Square {
+    fn write_buffer(&mut self, buffer: &mut [f32]) -> Result<usize> {
+        for e in buffer.iter_mut() {*e = self.next().unwrap()};
+        Ok(buffer.len())
+    }
+}
+
 
 /// Generate noise
 /// 
@@ -272,21 +390,25 @@ impl Iterator for Square {
 /// 
 /// ```
 /// use dsp::runtime::node::SourceNode;
-/// use dsp::node::generator::Noise;
+/// use dsp::core::generator::Noise;
 /// 
-/// let mut signal = Noise::new(4.0);
+/// let mut signal = Noise::new(4.0).unwrap();
 /// let mut buffer = vec![0.0;10];
 /// let _ = signal.write_buffer(&mut buffer);
 /// ```
 #[cfg(feature = "random")]
 pub struct Noise {
-    std: f32,
+    normal: Normal<f32>,
 }
 
 #[cfg(feature = "random")]
 impl Noise {
-    pub fn new(std: f32) -> Noise {
-        Noise { std }
+    /// Create Gaussian noise generator
+    ///   * std - Standard deviation. Must be finite and not negative
+    pub fn new(std: f32) -> Result<Noise> {
+        let normal = Normal::new(0.0, std)
+            .map_err(|err| Error::Config(err.to_string()))?;
+        Ok(Noise { normal })
     }
 }
 
@@ -296,20 +418,19 @@ impl Iterator for Noise {
     type Item = f32;
     
     fn next(&mut self) -> Option<Self::Item> {
-        let normal = Normal::new(0.0, self.std as f64).unwrap();
-        let sample = normal.sample(&mut rand::thread_rng()) as f32;
+        let sample = self.normal.sample(&mut rand::thread_rng());
         Some(sample)
     }
 }
 
 #[cfg(feature = "random")]
 impl SourceNode<f32> for Noise {
-    fn write_buffer(&mut self, buffer: &mut [f32]) -> Result<()> { 
-        let normal = Normal::new(0.0, self.std as f64).unwrap();
-        for i in 0..buffer.len() {
-            buffer[i] = normal.sample(&mut rand::thread_rng()) as f32;
+    fn write_buffer(&mut self, buffer: &mut [f32]) -> Result<usize> { 
+        let mut rng = rand::thread_rng();
+        for e in buffer.iter_mut() {
+            *e = self.normal.sample(&mut rng);
         }
-        Ok(())
+        Ok(buffer.len())
    }
 }
 
@@ -341,10 +462,6 @@ impl Chirp {
         let w = 2.0 * PI * (c/2.0*t.powi(2) + self.start_freq*t);
         f32::sin(w)
     }
-    
-    pub fn write_buffer(&mut self, buffer: &mut [f32]) { 
-        for e in buffer.iter_mut() {*e = self.next().unwrap()};
-    }
 }
 
 // Iterator implementation
@@ -361,15 +478,22 @@ impl Iterator for Chirp {
     }
 }
 
+impl SourceNode<f32> for Chirp {
+    fn write_buffer(&mut self, buffer: &mut [f32]) -> Result<usize> {
+        for e in buffer.iter_mut() {*e = self.next().unwrap()};
+        Ok(buffer.len())
+    }
+}
+
 
 /// ------------------------------------------------------------------------------------------------
 /// Module unit tests
 /// ------------------------------------------------------------------------------------------------
-
 #[cfg(test)]
 mod tests {
     use assert_approx_eq::assert_approx_eq;
-    use crate::core::generator::Sine;
+    use crate::core::generator::{Sine, Square};
+    use crate::runtime::node::SourceNode;
 
     #[test]
     fn test_sine_small_buffer() {
@@ -398,4 +522,25 @@ mod tests {
         assert_approx_eq!(buffer[2], 0.0, 1e-5f32);
         assert_approx_eq!(buffer[3], 1.0, 1e-5f32);
     }
+
+    #[test]
+    fn test_sine_change_frequency() {
+        let mut signal = Sine::new(2.0, 8);
+        let mut buffer = vec![0.0;2];
+        let _ = signal.write_buffer(&mut buffer);
+        signal.set_frequency(1.0);
+        let _ = signal.write_buffer(&mut buffer);
+        // Continues from phase π with the new step π/4
+        assert_approx_eq!(buffer[0], 0.0, 1e-5f32);
+        assert_approx_eq!(buffer[1], -0.70710677, 1e-5f32);
+    }
+
+    #[test]
+    fn test_square_set_phase() {
+        let mut signal = Square::new(4.0, 16);
+        signal.set_phase(std::f32::consts::PI);
+        let mut buffer = vec![0.0;4];
+        let _ = signal.write_buffer(&mut buffer);
+        assert_eq!(buffer, vec![-1.0, -1.0, 1.0, 1.0]);
+    }
 }
@@ -8,6 +8,8 @@ pub mod generator;
 pub mod fft;
 pub mod file;
 pub mod multiply;
+pub mod nco;
+pub mod stft;
 pub mod network;
 pub mod freq_shift;
 pub mod fm;
\ No newline at end of file
@@ -1,12 +1,17 @@
 //! Node for multiplying signal samples by constant value
 //! 
 
+use crate::Result;
+use crate::runtime::node::ProcessNode;
+use crate::sample::Sample;
+
 
 /// Multiply buffer sample by constant value
 /// 
 /// Example
 /// 
 /// ```
+/// use dsp::runtime::node::ProcessNode;
 /// use dsp::core::multiply::MultiplyConst;
 /// 
 /// let mut node = MultiplyConst::new(3.);
@@ -18,19 +23,22 @@
 /// assert_eq!(output_buffer[1], 6.);
 /// assert_eq!(output_buffer[2], 9.);
 /// ```
-pub struct MultiplyConst {
-    value: f32,
+pub struct MultiplyConst<T: Sample = f32> {
+    value: T::Real,
 }
 
-impl MultiplyConst {
-    pub fn new(value: f32) -> MultiplyConst {
+impl<T: Sample> MultiplyConst<T> {
+    pub fn new(value: T::Real) -> MultiplyConst<T> {
         MultiplyConst {value}
     }
+}
 
-    pub fn process_buffer(&mut self, input_buffer: &[f32], output_buffer: &mut [f32]) {
+impl<T: Sample> ProcessNode<T, T> for MultiplyConst<T> {
+    fn process_buffer(&mut self, input_buffer: &[T], output_buffer: &mut [T]) -> Result<usize> {
         let n = usize::min(input_buffer.len(), output_buffer.len());
         for i in 0..n {
-            output_buffer[i] = self.value * input_buffer[i]; 
+            output_buffer[i] = input_buffer[i] * self.value; 
         }
+        Ok(n)
     }
 }
@@ -0,0 +1,194 @@
+//! Numerically controlled oscillator
+//! 
+//! Phase is kept in the 32 bit fixed point accumulator, where the full circle is 2^32.
+//! The accumulator wraps around without any error, so the oscillator stays phase continuous 
+//! for any frequency and any number of samples.
+//! Frequency resolution is sample_rate / 2^32.
+//! 
+
+use std::f64::consts::PI;
+use crate::{Error, Result};
+
+
+const PHASE_SCALE: f64 = 4_294_967_296.0; // 2^32
+
+
+/// Numerically controlled oscillator
+/// 
+/// Example
+/// 
+/// ```
+/// use assert_approx_eq::assert_approx_eq;
+/// use dsp::core::nco::Nco;
+/// 
+/// let mut nco = Nco::new(2.0, 8);
+/// assert_approx_eq!(nco.sin(), 0.0, 1e-5f32);
+/// nco.step();
+/// assert_approx_eq!(nco.sin(), 1.0, 1e-5f32);
+/// 
+/// // Change frequency without discontinuity
+/// nco.set_frequency(1.0);
+/// nco.step();
+/// assert_approx_eq!(nco.sin(), 0.707, 1e-3f32);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Nco {
+    phase: u32,
+    phase_step: u32,
+    sample_rate: f64,
+    lut: Option<Vec<f32>>,
+}
+
+impl Nco {
+    /// Create new oscillator
+    ///   * freq - oscillator frequency. Can be negative
+    ///   * sample_rate - Number of samples/s
+    pub fn new(freq: f32, sample_rate: usize) -> Nco {
+        let mut nco = Nco { phase: 0, phase_step: 0, sample_rate: sample_rate as f64, lut: None };
+        nco.set_frequency(freq);
+        nco
+    }
+
+    /// Create oscillator which calculates sin and cos from the lookup table 
+    /// instead of calling the math functions.
+    ///   * lut_bits - The table has 2^lut_bits samples of a single sine period. Range 1..=24
+    pub fn with_lut(freq: f32, sample_rate: usize, lut_bits: u32) -> Result<Nco> {
+        if !(1..=24).contains(&lut_bits) {
+            return Err(Error::Config(format!("LUT bits {} should be in range 1..=24", lut_bits)));
+        }
+        let size = 1usize << lut_bits;
+        let lut = (0..size)
+            .map(|i| (2.0 * PI * i as f64 / size as f64).sin() as f32)
+            .collect();
+        let mut nco = Nco::new(freq, sample_rate);
+        nco.lut = Some(lut);
+        Ok(nco)
+    }
+
+    /// Change frequency. The phase is preserved, so there is no discontinuity in the signal.
+    pub fn set_frequency(&mut self, freq: f32) {
+        let step = (freq as f64 / self.sample_rate * PHASE_SCALE).round();
+        // Negative frequency wraps around to the step larger then half of the circle
+        self.phase_step = (step as i64) as u32;
+    }
+
+    /// Current frequency
+    pub fn frequency(&self) -> f32 {
+        (self.phase_step as i32 as f64 * self.sample_rate / PHASE_SCALE) as f32
+    }
+
+    /// Set phase in radians
+    pub fn set_phase(&mut self, phase: f32) {
+        let cycles = (phase as f64 / (2.0 * PI)).rem_euclid(1.0);
+        self.phase = (cycles * PHASE_SCALE) as u64 as u32;
+    }
+
+    /// Current phase in radians in range [0, 2π)
+    pub fn phase(&self) -> f32 {
+        (2.0 * PI * self.cycle_pos()) as f32
+    }
+
+    /// Current position in the cycle in range [0, 1)
+    pub fn cycle_pos(&self) -> f64 {
+        self.phase as f64 / PHASE_SCALE
+    }
+
+    /// Move phase to the next sample
+    pub fn step(&mut self) {
+        self.phase = self.phase.wrapping_add(self.phase_step);
+    }
+
+    /// Sine of the current phase
+    pub fn sin(&self) -> f32 {
+        match &self.lut {
+            Some(lut) => lut_value(lut, self.phase),
+            None => self.phase().sin(),
+        }
+    }
+
+    /// Cosine of the current phase
+    pub fn cos(&self) -> f32 {
+        match &self.lut {
+            // cos(x) = sin(x + π/2)
+            Some(lut) => lut_value(lut, self.phase.wrapping_add(1 << 30)),
+            None => self.phase().cos(),
+        }
+    }
+}
+
+/// Read LUT using the top bits of the phase
+fn lut_value(lut: &[f32], phase: u32) -> f32 {
+    let shift = 32 - lut.len().trailing_zeros();
+    lut[(phase >> shift) as usize]
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use super::*;
+
+    #[test]
+    fn test_non_integer_frequency() {
+        // 1000.5 Hz would loose phase continuity with the sample counter wrapping at sample rate
+        let freq = 1000.5;
+        let sample_rate = 48_000;
+        let mut nco = Nco::new(freq, sample_rate);
+        for i in 0..10 * sample_rate {
+            let expected = (2.0 * PI * freq as f64 * i as f64 / sample_rate as f64).sin() as f32;
+            assert_approx_eq!(nco.sin(), expected, 1e-3f32);
+            nco.step();
+        }
+    }
+
+    #[test]
+    fn test_set_frequency_is_continuous() {
+        let mut nco = Nco::new(10.0, 100);
+        for _ in 0..5 {
+            nco.step();
+        }
+        let phase = nco.phase();
+        nco.set_frequency(20.0);
+        assert_approx_eq!(nco.phase(), phase, 1e-6f32);
+        nco.step();
+        assert_approx_eq!(nco.phase(), phase + 2.0 * std::f32::consts::PI * 0.2, 1e-5f32);
+    }
+
+    #[test]
+    fn test_negative_frequency() {
+        let mut nco = Nco::new(-2.0, 8);
+        assert_approx_eq!(nco.frequency(), -2.0, 1e-6f32);
+        nco.step();
+        assert_approx_eq!(nco.sin(), -1.0, 1e-5f32);
+        assert_approx_eq!(nco.cos(), 0.0, 1e-5f32);
+    }
+
+    #[test]
+    fn test_set_phase() {
+        let mut nco = Nco::new(0.0, 8);
+        nco.set_phase(-std::f32::consts::FRAC_PI_2);
+        assert_approx_eq!(nco.phase(), 1.5 * std::f32::consts::PI, 1e-5f32);
+        assert_approx_eq!(nco.sin(), -1.0, 1e-5f32);
+    }
+
+    #[test]
+    fn test_lut() {
+        let mut nco = Nco::new(440.0, 44_100);
+        let mut lut_nco = Nco::with_lut(440.0, 44_100, 12).unwrap();
+        for _ in 0..44_100 {
+            assert_approx_eq!(lut_nco.sin(), nco.sin(), 2e-3f32);
+            assert_approx_eq!(lut_nco.cos(), nco.cos(), 2e-3f32);
+            nco.step();
+            lut_nco.step();
+        }
+    }
+
+    #[test]
+    fn test_invalid_lut_bits() {
+        assert!(matches!(Nco::with_lut(440.0, 44_100, 0), Err(Error::Config(_))));
+        assert!(matches!(Nco::with_lut(440.0, 44_100, 25), Err(Error::Config(_))));
+    }
+}
@@ -2,6 +2,8 @@
 //! 
 use std::net::UdpSocket;
 use byteorder::{ByteOrder, LittleEndian};
+use crate::{Error, Result};
+use crate::runtime::node::{SinkNode, SourceNode};
 
 
 /// Send binary data via the TCP sockek
@@ -9,11 +11,12 @@ use byteorder::{ByteOrder, LittleEndian};
 /// Example
 /// 
 /// ```
+/// use dsp::runtime::node::SinkNode;
 /// use dsp::core::network::UdpSink;
 /// 
-/// let mut node = UdpSink::new(3456, "127.0.0.1:1234");
+/// let mut node = UdpSink::new(3456, "127.0.0.1:1234").unwrap();
 /// let input_buffer = vec![1.;100];
-/// node.read_buffer(&input_buffer);
+/// node.read_buffer(&input_buffer).unwrap();
 /// ```
 pub struct UdpSink {
     socket: UdpSocket,
@@ -21,16 +24,19 @@ pub struct UdpSink {
 }
 
 impl UdpSink {
-    pub fn new(port: u32, addr: &str) -> UdpSink {
+    pub fn new(port: u32, addr: &str) -> Result<UdpSink> {
         let bind_addr = format!("127.0.0.1:{}", port);
-        let socket = UdpSocket::bind(bind_addr).expect("Failed to bind UdpSink"); 
-        UdpSink {socket, addr: addr.to_owned()}
+        let socket = UdpSocket::bind(bind_addr)?; 
+        Ok(UdpSink {socket, addr: addr.to_owned()})
     }
+}
 
-    pub fn read_buffer(&mut self, input_buffer: &[f32]) {
+impl SinkNode<f32> for UdpSink {
+    fn read_buffer(&mut self, input_buffer: &[f32]) -> Result<()> {
         let mut bytes: Vec<u8> = vec![0; 4 * input_buffer.len()];
-        LittleEndian::write_f32_into(&input_buffer, &mut bytes);
-        self.socket.send_to(&bytes, &self.addr).unwrap();
+        LittleEndian::write_f32_into(input_buffer, &mut bytes);
+        self.socket.send_to(&bytes, &self.addr)?;
+        Ok(())
     }
 }
 
@@ -43,13 +49,20 @@ pub struct UdpSource {
 }
 
 impl UdpSource {
-    pub fn new(port: usize, buffer_size: usize) -> UdpSource {
-        let socket = UdpSocket::bind(format!("127.0.0.1:{}", port)).expect("Can't bind to udp socket");
-        UdpSource {socket, bytes: vec![0; 4*buffer_size]}
+    pub fn new(port: usize, buffer_size: usize) -> Result<UdpSource> {
+        let socket = UdpSocket::bind(format!("127.0.0.1:{}", port))?;
+        Ok(UdpSource {socket, bytes: vec![0; 4*buffer_size]})
     }
+}
 
-    pub fn write_buffer(&mut self, output_buffer: &mut [f32]) {
-        let _ = self.socket.recv_from(&mut self.bytes).expect("Error reading from socket");
-        LittleEndian::read_f32_into(&self.bytes, output_buffer);
+impl SourceNode<f32> for UdpSource {
+    fn write_buffer(&mut self, output_buffer: &mut [f32]) -> Result<usize> {
+        let (size, _) = self.socket.recv_from(&mut self.bytes)?;
+        if size % 4 != 0 {
+            return Err(Error::Format(format!("Received {} bytes which is not a multiple of f32 size", size)));
+        }
+        let n = usize::min(size / 4, output_buffer.len());
+        LittleEndian::read_f32_into(&self.bytes[..4*n], &mut output_buffer[..n]);
+        Ok(n)
     }
 }
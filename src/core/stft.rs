@@ -0,0 +1,385 @@
+//! Short-time Fourier transform
+//!
+//! `Stft` splits the real signal into overlapping windowed frames and returns N/2+1 bins
+//! for each frame. Frames are produced incrementally, so the input can be split into buffers
+//! of any size. `Istft` resynthesizes the signal with the weighted overlap-add.
+//!
+//! Example
+//!
+//! ```
+//! use dsp::runtime::node::ProcessNode;
+//! use dsp::core::stft::{Stft, Istft};
+//! use dsp::num_complex::Complex32;
+//! use dsp::window;
+//!
+//! let mut stft = Stft::new(64, 16, window::hann(64)).unwrap();
+//! let mut istft = Istft::new(64, 16, window::hann(64)).unwrap();
+//! let input: Vec<f32> = (0..256).map(|i| (0.1 * i as f32).sin()).collect();
+//! let mut spectrum = vec![Complex32::new(0., 0.); stft.output_size(input.len())];
+//! let n = stft.process_buffer(&input, &mut spectrum).unwrap();
+//! assert_eq!(n, 16 * stft.num_bins());
+//!
+//! // Spectral processing goes here
+//!
+//! let mut output = vec![0.0; istft.output_size(n)];
+//! assert_eq!(istft.process_buffer(&spectrum[..n], &mut output).unwrap(), 256);
+//! // Output is delayed by fft_size - hop_size samples
+//! for i in istft.delay()..256 {
+//!     assert!((output[i] - input[i - istft.delay()]).abs() < 1e-4);
+//! }
+//! ```
+
+use crate::num_complex::Complex32;
+use crate::{Error, Result};
+use crate::core::fft::{InverseRealFFT, Normalization, RealFFT, WindowType};
+use crate::runtime::node::ProcessNode;
+use crate::window::Window;
+
+
+/// Streaming STFT of the real signal.
+/// Output contains frames of N/2+1 bins, one frame per hop_size input samples.
+/// Signal is preceded by N - hop_size zeros, so the first frame is ready after hop_size samples.
+pub struct Stft {
+    hop_size: usize,
+    window: Window,
+    fft: RealFFT,
+    // Last N samples, frame is computed when the buffer is full
+    buffer: Vec<f32>,
+    frame: Vec<f32>,
+}
+
+impl Stft {
+    /// Create STFT
+    ///   * fft_size - Size of the frame N
+    ///   * hop_size - Number of samples between frames. Has to be in range 1..=fft_size
+    ///   * window - Analysis window of size fft_size, e.g. `window::hann(fft_size)`
+    pub fn new(fft_size: usize, hop_size: usize, window: Window) -> Result<Stft> {
+        check_params(fft_size, hop_size, &window)?;
+        Ok(Stft {
+            hop_size,
+            window,
+            fft: RealFFT::new(fft_size, WindowType::Rectangular),
+            buffer: vec![0.0; fft_size - hop_size],
+            frame: vec![0.0; fft_size],
+        })
+    }
+
+    /// Size of the frame N
+    pub fn fft_size(&self) -> usize {
+        self.frame.len()
+    }
+
+    /// Number of samples between frames
+    pub fn hop_size(&self) -> usize {
+        self.hop_size
+    }
+
+    /// Number of bins in each output frame: N/2+1
+    pub fn num_bins(&self) -> usize {
+        self.fft_size() / 2 + 1
+    }
+
+    /// Drop buffered samples
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.buffer.resize(self.fft_size() - self.hop_size, 0.0);
+    }
+
+    /// Number of frames which will be produced from the given number of samples
+    fn num_frames(&self, input_size: usize) -> usize {
+        (self.buffer.len() + input_size + self.hop_size - self.fft_size()) / self.hop_size
+    }
+}
+
+impl ProcessNode<f32, Complex32> for Stft {
+    /// Returns number of written bins, which is a multiple of `num_bins`
+    fn process_buffer(&mut self, input_buffer: &[f32], output_buffer: &mut [Complex32]) -> Result<usize> {
+        let bins = self.num_bins();
+        let frames = self.num_frames(input_buffer.len());
+        check_output(output_buffer.len(), frames * bins)?;
+        let n = self.fft_size();
+        self.buffer.extend_from_slice(input_buffer);
+        for out in output_buffer.chunks_exact_mut(bins).take(frames) {
+            self.window.apply(&self.buffer[..n], &mut self.frame);
+            self.fft.process_buffer(&self.frame, out)?;
+            self.buffer.drain(..self.hop_size);
+        }
+        Ok(frames * bins)
+    }
+
+    /// Upper bound, which doesn't depend on the number of buffered samples
+    fn output_size(&self, input_size: usize) -> usize {
+        input_size.div_ceil(self.hop_size) * self.num_bins()
+    }
+}
+
+
+/// Inverse STFT with weighted overlap-add.
+/// Each frame of N/2+1 bins is transformed back, multiplied by the synthesis window
+/// and added to the previous frames. Output is normalized by the overlapped product of the
+/// analysis and synthesis windows, so the unmodified spectrum reconstructs the signal
+/// delayed by N - hop_size samples.
+pub struct Istft {
+    hop_size: usize,
+    window: Window,
+    ifft: InverseRealFFT,
+    // 1 / sum of w_a * w_s over the overlapping frames for each output position
+    norm: Vec<f32>,
+    // Bins of the incomplete frame
+    pending: Vec<Complex32>,
+    frame: Vec<f32>,
+    overlap: Vec<f32>,
+}
+
+impl Istft {
+    /// Create ISTFT with the same analysis and synthesis window
+    ///   * fft_size - Size of the frame N
+    ///   * hop_size - Number of samples between frames
+    ///   * window - Window used by the `Stft`
+    pub fn new(fft_size: usize, hop_size: usize, window: Window) -> Result<Istft> {
+        let analysis = window.clone();
+        Istft::with_windows(fft_size, hop_size, &analysis, window)
+    }
+
+    /// Create ISTFT with separate windows
+    ///   * analysis - Window used by the `Stft`
+    ///   * synthesis - Window applied to the resynthesized frames
+    pub fn with_windows(fft_size: usize, hop_size: usize, analysis: &Window, synthesis: Window) -> Result<Istft> {
+        check_params(fft_size, hop_size, analysis)?;
+        check_params(fft_size, hop_size, &synthesis)?;
+        let mut norm = vec![0.0; hop_size];
+        for i in 0..fft_size {
+            norm[i % hop_size] += analysis.as_slice()[i] * synthesis.as_slice()[i];
+        }
+        if norm.iter().any(|s| s.abs() < 1e-6) {
+            return Err(Error::Config("Windows don't overlap, signal can't be reconstructed".to_string()));
+        }
+        Ok(Istft {
+            hop_size,
+            window: synthesis,
+            ifft: InverseRealFFT::new(fft_size).with_normalization(Normalization::N),
+            norm: norm.iter().map(|s| 1.0 / s).collect(),
+            pending: Vec::with_capacity(fft_size / 2 + 1),
+            frame: vec![0.0; fft_size],
+            overlap: vec![0.0; fft_size],
+        })
+    }
+
+    /// Size of the frame N
+    pub fn fft_size(&self) -> usize {
+        self.frame.len()
+    }
+
+    /// Number of samples between frames
+    pub fn hop_size(&self) -> usize {
+        self.hop_size
+    }
+
+    /// Number of bins in each input frame: N/2+1
+    pub fn num_bins(&self) -> usize {
+        self.fft_size() / 2 + 1
+    }
+
+    /// Delay of the `Stft` -> `Istft` chain in samples
+    pub fn delay(&self) -> usize {
+        self.fft_size() - self.hop_size
+    }
+
+    /// Clear incomplete frame and overlapped samples
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.overlap.fill(0.0);
+    }
+
+    /// Add frame to the overlap buffer and write hop_size completed samples
+    fn synthesize(&mut self, bins: &[Complex32], output: &mut [f32]) -> Result<()> {
+        self.ifft.process_buffer(bins, &mut self.frame)?;
+        for (i, y) in self.overlap.iter_mut().enumerate() {
+            *y += self.frame[i] * self.window.as_slice()[i];
+        }
+        for (i, y) in output.iter_mut().enumerate() {
+            *y = self.overlap[i] * self.norm[i];
+        }
+        self.overlap.copy_within(self.hop_size.., 0);
+        let n = self.overlap.len();
+        self.overlap[n - self.hop_size..].fill(0.0);
+        Ok(())
+    }
+}
+
+impl ProcessNode<Complex32, f32> for Istft {
+    /// Frames can be split between the buffers. Returns number of written samples
+    fn process_buffer(&mut self, input_buffer: &[Complex32], output_buffer: &mut [f32]) -> Result<usize> {
+        let bins = self.num_bins();
+        let frames = (self.pending.len() + input_buffer.len()) / bins;
+        check_output(output_buffer.len(), frames * self.hop_size)?;
+        let mut input = input_buffer;
+        let mut outputs = output_buffer.chunks_exact_mut(self.hop_size);
+        if !self.pending.is_empty() {
+            let k = usize::min(bins - self.pending.len(), input.len());
+            self.pending.extend_from_slice(&input[..k]);
+            input = &input[k..];
+            if self.pending.len() < bins {
+                return Ok(0);
+            }
+            let pending = std::mem::take(&mut self.pending);
+            self.synthesize(&pending, outputs.next().unwrap())?;
+            self.pending = pending;
+            self.pending.clear();
+        }
+        let mut frames_iter = input.chunks_exact(bins);
+        for frame in frames_iter.by_ref() {
+            self.synthesize(frame, outputs.next().unwrap())?;
+        }
+        self.pending.extend_from_slice(frames_iter.remainder());
+        Ok(frames * self.hop_size)
+    }
+
+    /// Upper bound, which doesn't depend on the number of buffered bins
+    fn output_size(&self, input_size: usize) -> usize {
+        input_size.div_ceil(self.num_bins()) * self.hop_size
+    }
+}
+
+
+/// Check constant overlap-add (COLA) condition: sum of the windows shifted by hop_size
+/// is constant. For the weighted overlap-add pass the product of the analysis and synthesis window.
+///
+/// Example
+///
+/// ```
+/// use dsp::core::stft::is_cola;
+/// use dsp::window;
+///
+/// // Periodic Hann window
+/// let hann: Vec<f32> = window::hann(65).samples[..64].to_vec();
+/// assert!(is_cola(&hann, 32));
+/// assert!(!is_cola(&hann, 48));
+/// ```
+pub fn is_cola(window: &[f32], hop_size: usize) -> bool {
+    if hop_size == 0 || hop_size > window.len() {
+        return false;
+    }
+    let mut sums = vec![0.0; hop_size];
+    for (i, w) in window.iter().enumerate() {
+        sums[i % hop_size] += w;
+    }
+    let mean = sums.iter().sum::<f32>() / hop_size as f32;
+    mean.abs() > 1e-6 && sums.iter().all(|s| (s - mean).abs() <= 1e-3 * mean.abs())
+}
+
+
+fn check_params(fft_size: usize, hop_size: usize, window: &Window) -> Result<()> {
+    if fft_size == 0 || hop_size == 0 || hop_size > fft_size {
+        Err(Error::Config(format!("Hop size {} has to be in range 1..={}", hop_size, fft_size)))
+    } else if window.len() != fft_size {
+        Err(Error::Config(format!("Window has size {} but FFT size is {}", window.len(), fft_size)))
+    } else {
+        Ok(())
+    }
+}
+
+fn check_output(buffer_size: usize, required: usize) -> Result<()> {
+    if buffer_size < required {
+        Err(Error::Config(format!("Output buffer has size {} but {} samples are required", buffer_size, required)))
+    } else {
+        Ok(())
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use crate::window;
+    use super::*;
+
+    fn signal(size: usize) -> Vec<f32> {
+        (0..size).map(|i| (0.05 * i as f32).sin() + 0.5 * (0.7 * i as f32).cos()).collect()
+    }
+
+    #[test]
+    fn test_frames_from_any_buffer_size() {
+        let input = signal(200);
+        let mut stft = Stft::new(32, 8, window::hann(32)).unwrap();
+        let mut expected = vec![Complex32::default(); stft.output_size(200)];
+        let n = stft.process_buffer(&input, &mut expected).unwrap();
+        assert_eq!(n, 25 * 17);
+
+        stft.reset();
+        let mut output = vec![];
+        for chunk in input.chunks(7) {
+            let mut buffer = vec![Complex32::default(); stft.output_size(chunk.len())];
+            let k = stft.process_buffer(chunk, &mut buffer).unwrap();
+            output.extend_from_slice(&buffer[..k]);
+        }
+        assert_eq!(output, expected[..n]);
+    }
+
+    #[test]
+    fn test_frame_spectrum() {
+        // Frame 3 covers samples 0..16 with rectangular window
+        let input: Vec<f32> = (0..16).map(|i| i as f32).collect();
+        let mut stft = Stft::new(16, 4, window::rectangular(16)).unwrap();
+        let mut output = vec![Complex32::default(); stft.output_size(16)];
+        assert_eq!(stft.process_buffer(&input, &mut output).unwrap(), 4 * 9);
+        assert_approx_eq!(output[3 * 9].re, 120.0, 1e-3);
+        assert_approx_eq!(output[0].re, 0.0 + 1.0 + 2.0 + 3.0, 1e-3);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let input = signal(500);
+        for (n, hop, analysis, synthesis) in [
+            (64, 16, window::hann(64), window::hann(64)),
+            (64, 32, window::hamming(64), window::rectangular(64)),
+            (63, 21, window::blackman(63), window::blackman(63)),
+            (32, 32, window::rectangular(32), window::rectangular(32)),
+        ] {
+            let mut stft = Stft::new(n, hop, analysis.clone()).unwrap();
+            let mut istft = Istft::with_windows(n, hop, &analysis, synthesis).unwrap();
+            let mut output = vec![];
+            for chunk in input.chunks(50) {
+                let mut spectrum = vec![Complex32::default(); stft.output_size(chunk.len())];
+                let k = stft.process_buffer(chunk, &mut spectrum).unwrap();
+                // Frames split between the buffers
+                for bins in spectrum[..k].chunks(10) {
+                    let mut buffer = vec![0.0; istft.output_size(bins.len())];
+                    let m = istft.process_buffer(bins, &mut buffer).unwrap();
+                    output.extend_from_slice(&buffer[..m]);
+                }
+            }
+            let delay = istft.delay();
+            assert_eq!(output.len(), input.len() / hop * hop);
+            for i in 0..output.len() {
+                let expected = if i < delay { 0.0 } else { input[i - delay] };
+                assert_approx_eq!(output[i], expected, 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cola() {
+        let periodic_hann = &window::hann(17).samples[..16];
+        assert!(is_cola(periodic_hann, 8));
+        assert!(is_cola(periodic_hann, 4));
+        assert!(!is_cola(periodic_hann, 16));
+        assert!(is_cola(&window::rectangular(16).samples, 16));
+        assert!(!is_cola(periodic_hann, 0));
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert!(Stft::new(32, 0, window::hann(32)).is_err());
+        assert!(Stft::new(32, 33, window::hann(32)).is_err());
+        assert!(Stft::new(32, 8, window::hann(16)).is_err());
+        // Zero at the edges of the hann window is not covered by the other frames
+        assert!(Istft::new(32, 32, window::hann(32)).is_err());
+        let mut stft = Stft::new(32, 8, window::hann(32)).unwrap();
+        let mut output = vec![Complex32::default(); 17];
+        assert!(stft.process_buffer(&[0.0; 16], &mut output).is_err());
+    }
+}
@@ -0,0 +1,52 @@
+//! Error type returned by the nodes
+//! 
+
+use std::fmt;
+use std::io;
+
+
+/// Errors reported by the nodes
+#[derive(Debug)]
+pub enum Error {
+    /// Reading or writing the underlying file or socket failed
+    Io(io::Error),
+    /// Data read by the node is not in the expected format
+    Format(String),
+    /// Node parameters or buffer sizes are not valid
+    Config(String),
+    /// Node running on its own thread panicked
+    NodePanic(String),
+    /// Source has no more samples
+    EndOfStream,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "I/O error: {}", err),
+            Error::Format(msg) => write!(f, "Format error: {}", msg),
+            Error::Config(msg) => write!(f, "Configuration error: {}", msg),
+            Error::NodePanic(msg) => write!(f, "Node panicked: {}", msg),
+            Error::EndOfStream => write!(f, "End of stream"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+
+/// Result type used by the nodes
+pub type Result<T> = std::result::Result<T, Error>;
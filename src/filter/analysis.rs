@@ -0,0 +1,356 @@
+//! Filter analysis
+//!
+//! Frequency and time domain response, poles and zeros calculated from the filter coefficients.
+//! Any filter which can describe itself as a cascade of transfer functions
+//! (FIR, biquad, SOS) implements the `FilterResponse` trait.
+//!
+//! Example
+//!
+//! ```
+//! use dsp::filter::analysis::FilterResponse;
+//! use dsp::filter::biquad::{BiquadFilter, Width};
+//!
+//! let filter: BiquadFilter = BiquadFilter::lowpass(1_000.0, Width::Q(0.707), 8_000).unwrap();
+//! let gain = filter.magnitude_db(&[0.0, 1_000.0], 8_000);
+//! assert!(gain[0].abs() < 1e-3);
+//! assert!((gain[1] + 3.0).abs() < 0.1);
+//! ```
+
+use std::f64::consts::PI;
+use num_complex::{Complex32, Complex64};
+use num_traits::{cast, Float, ToPrimitive};
+use crate::filter::biquad::BiquadFilter;
+use crate::filter::design::iir::Zpk;
+use crate::filter::design::poly;
+use crate::filter::fir::FirFilter;
+use crate::filter::sos::SosFilter;
+use crate::sample::Sample;
+
+
+/// Response of the linear filter
+pub trait FilterResponse {
+    /// Filter as a cascade of transfer functions (b, a).
+    /// Coefficients are polynomials in z^-1.
+    fn transfer_function(&self) -> Vec<(Vec<f64>, Vec<f64>)>;
+
+    /// Complex frequency response at the given frequencies (Hz)
+    fn freqz(&self, freqs: &[f32], sample_rate: usize) -> Vec<Complex32> {
+        let tf = self.transfer_function();
+        freqs.iter()
+            .map(|f| {
+                let h = response(&tf, omega(*f, sample_rate));
+                Complex32::new(h.re as f32, h.im as f32)
+            })
+            .collect()
+    }
+
+    /// Magnitude response in dB at the given frequencies (Hz)
+    fn magnitude_db(&self, freqs: &[f32], sample_rate: usize) -> Vec<f32> {
+        let tf = self.transfer_function();
+        freqs.iter()
+            .map(|f| (20.0 * response(&tf, omega(*f, sample_rate)).norm().log10()) as f32)
+            .collect()
+    }
+
+    /// Unwrapped phase response in radians at the given (increasing) frequencies
+    fn phase(&self, freqs: &[f32], sample_rate: usize) -> Vec<f32> {
+        let tf = self.transfer_function();
+        let mut phase: Vec<f32> = freqs.iter()
+            .map(|f| response(&tf, omega(*f, sample_rate)).arg() as f32)
+            .collect();
+        unwrap(&mut phase);
+        phase
+    }
+
+    /// Group delay in samples at the given frequencies (Hz).
+    /// Group delay is not defined at the zeros on the unit circle.
+    fn group_delay(&self, freqs: &[f32], sample_rate: usize) -> Vec<f32> {
+        let tf = self.transfer_function();
+        freqs.iter()
+            .map(|f| {
+                let w = omega(*f, sample_rate);
+                tf.iter().map(|(b, a)| poly_delay(b, w) - poly_delay(a, w)).sum::<f64>() as f32
+            })
+            .collect()
+    }
+
+    /// Phase delay -phase(w)/w in samples at the given (increasing) frequencies.
+    /// At 0 Hz it is equal to the group delay.
+    fn phase_delay(&self, freqs: &[f32], sample_rate: usize) -> Vec<f32> {
+        let phase = self.phase(freqs, sample_rate);
+        let group_delay = self.group_delay(&[0.0], sample_rate)[0];
+        freqs.iter().zip(phase.iter())
+            .map(|(f, p)| if *f == 0.0 { group_delay } else { -p / omega(*f, sample_rate) as f32 })
+            .collect()
+    }
+
+    /// First `length` samples of the impulse response
+    fn impulse_response(&self, length: usize) -> Vec<f32> {
+        let mut input = vec![0.0; length];
+        if length > 0 {
+            input[0] = 1.0;
+        }
+        simulate(&self.transfer_function(), &input)
+    }
+
+    /// First `length` samples of the step response
+    fn step_response(&self, length: usize) -> Vec<f32> {
+        simulate(&self.transfer_function(), &vec![1.0; length])
+    }
+
+    /// Zeros of the filter in the z-plane
+    fn zeros(&self) -> Vec<Complex64> {
+        self.transfer_function().iter()
+            .flat_map(|(b, a)| section_roots(b, a))
+            .collect()
+    }
+
+    /// Poles of the filter in the z-plane
+    fn poles(&self) -> Vec<Complex64> {
+        self.transfer_function().iter()
+            .flat_map(|(b, a)| section_roots(a, b))
+            .collect()
+    }
+
+    /// Filter is stable if all its poles are inside the unit circle
+    fn is_stable(&self) -> bool {
+        self.poles().iter().all(|p| p.norm() < 1.0)
+    }
+}
+
+
+impl<T: Sample> FilterResponse for BiquadFilter<T> {
+    fn transfer_function(&self) -> Vec<(Vec<f64>, Vec<f64>)> {
+        let (b, a) = self.coefficients();
+        vec![(to_f64(&b), to_f64(&a))]
+    }
+}
+
+impl<T: Sample> FilterResponse for SosFilter<T> {
+    fn transfer_function(&self) -> Vec<(Vec<f64>, Vec<f64>)> {
+        let mut tf: Vec<(Vec<f64>, Vec<f64>)> = self.sections().iter()
+            .map(|s| (to_f64(&s[0..3]), to_f64(&s[3..6])))
+            .collect();
+        tf.push((to_f64(&[self.gain()]), vec![1.0]));
+        tf
+    }
+}
+
+impl<T: Sample> FilterResponse for FirFilter<T> {
+    fn transfer_function(&self) -> Vec<(Vec<f64>, Vec<f64>)> {
+        vec![(to_f64(&self.taps()), vec![1.0])]
+    }
+}
+
+impl<T: Sample> FilterResponse for Vec<BiquadFilter<T>> {
+    fn transfer_function(&self) -> Vec<(Vec<f64>, Vec<f64>)> {
+        self.iter().flat_map(|f| f.transfer_function()).collect()
+    }
+}
+
+/// Digital filter
+impl FilterResponse for Zpk {
+    fn transfer_function(&self) -> Vec<(Vec<f64>, Vec<f64>)> {
+        self.sections().iter().map(|(b, a)| (b.to_vec(), a.to_vec())).collect()
+    }
+}
+
+
+/// Unwrap phase, by adding multiples of 2π when the jump between consecutive values is greater then π
+pub fn unwrap<R: Float>(phase: &mut [R]) {
+    let pi: R = cast(PI).unwrap();
+    let two_pi = pi + pi;
+    let mut offset = R::zero();
+    for i in 1..phase.len() {
+        let prev = phase[i - 1];
+        let mut value = phase[i] + offset;
+        let delta = value - prev;
+        if delta.abs() > pi {
+            let k = (delta / two_pi).round();
+            offset = offset - k * two_pi;
+            value = value - k * two_pi;
+        }
+        phase[i] = value;
+    }
+}
+
+
+/// Normalized angular frequency
+fn omega(freq: f32, sample_rate: usize) -> f64 {
+    2.0 * PI * freq as f64 / sample_rate as f64
+}
+
+/// Evaluate polynomial in z^-1 at z = e^jw
+fn poly_eval(coeffs: &[f64], w: f64) -> Complex64 {
+    coeffs.iter().enumerate()
+        .map(|(k, c)| Complex64::from_polar(*c, -w * k as f64))
+        .sum()
+}
+
+/// Group delay of the polynomial in z^-1: Re(sum k*c_k*z^-k / sum c_k*z^-k)
+fn poly_delay(coeffs: &[f64], w: f64) -> f64 {
+    let ramp: Vec<f64> = coeffs.iter().enumerate().map(|(k, c)| k as f64 * c).collect();
+    (poly_eval(&ramp, w) / poly_eval(coeffs, w)).re
+}
+
+fn response(tf: &[(Vec<f64>, Vec<f64>)], w: f64) -> Complex64 {
+    tf.iter()
+        .map(|(b, a)| poly_eval(b, w) / poly_eval(a, w))
+        .product()
+}
+
+/// Filter the signal with the cascade of transfer functions in direct form
+fn simulate(tf: &[(Vec<f64>, Vec<f64>)], input: &[f64]) -> Vec<f32> {
+    let mut signal = input.to_vec();
+    for (b, a) in tf {
+        let mut output = vec![0.0; signal.len()];
+        for n in 0..signal.len() {
+            let mut acc = 0.0;
+            for (k, bk) in b.iter().enumerate().take(n + 1) {
+                acc += bk * signal[n - k];
+            }
+            for (k, ak) in a.iter().enumerate().take(n + 1).skip(1) {
+                acc -= ak * output[n - k];
+            }
+            output[n] = acc / a[0];
+        }
+        signal = output;
+    }
+    signal.iter().map(|v| *v as f32).collect()
+}
+
+/// Roots in the z-plane of the numerator or denominator of the section.
+/// Trailing zeros are removed, so the pole/zero pairs at the origin cancel out
+/// and only the difference in the degree adds roots at the origin.
+fn section_roots(coeffs: &[f64], other: &[f64]) -> Vec<Complex64> {
+    let coeffs = trim_zeros(coeffs);
+    let mut roots = poly::roots(coeffs);
+    let origin = trim_zeros(other).len().saturating_sub(coeffs.len());
+    roots.extend(vec![Complex64::new(0.0, 0.0); origin]);
+    roots
+}
+
+fn trim_zeros(coeffs: &[f64]) -> &[f64] {
+    let end = coeffs.iter().rposition(|c| *c != 0.0).map_or(0, |i| i + 1);
+    &coeffs[..end]
+}
+
+fn to_f64<R: ToPrimitive>(xs: &[R]) -> Vec<f64> {
+    xs.iter().map(|x| x.to_f64().unwrap()).collect()
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use crate::filter::biquad::Width;
+    use crate::filter::design::iir::{self, Band, Prototype};
+    use crate::runtime::node::ProcessNode;
+    use super::*;
+
+    const SAMPLE_RATE: usize = 8_000;
+
+    #[test]
+    fn test_fir_delay() {
+        // Symmetric FIR has constant group delay (N-1)/2
+        let filter: FirFilter = FirFilter::new(&[0.1, 0.2, 0.4, 0.2, 0.1]).unwrap();
+        let freqs = [0.0, 500.0, 1_000.0, 2_000.0];
+        for d in filter.group_delay(&freqs, SAMPLE_RATE) {
+            assert_approx_eq!(d, 2.0, 1e-5);
+        }
+        for d in filter.phase_delay(&freqs, SAMPLE_RATE) {
+            assert_approx_eq!(d, 2.0, 1e-5);
+        }
+        let h = filter.freqz(&[0.0], SAMPLE_RATE);
+        assert_approx_eq!(h[0].re, 1.0, 1e-6);
+        assert_eq!(filter.impulse_response(7), vec![0.1, 0.2, 0.4, 0.2, 0.1, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_biquad_response() {
+        let filter: BiquadFilter = BiquadFilter::lowpass(1_000.0, Width::Q(0.5f32.sqrt()), SAMPLE_RATE).unwrap();
+        let db = filter.magnitude_db(&[0.0, 1_000.0], SAMPLE_RATE);
+        assert_approx_eq!(db[0], 0.0, 1e-4);
+        assert_approx_eq!(db[1], -3.0103, 1e-3);
+        // 2nd order lowpass has phase -π/2 at cutoff
+        let freqs: Vec<f32> = (0..=10).map(|i| i as f32 * 100.0).collect();
+        let phase = filter.phase(&freqs, SAMPLE_RATE);
+        assert_approx_eq!(phase[10], -std::f32::consts::FRAC_PI_2, 1e-4);
+    }
+
+    #[test]
+    fn test_impulse_and_step_response() {
+        let zpk = iir::digital(Prototype::Butterworth, 4, Band::Lowpass(1_000.0), SAMPLE_RATE).unwrap();
+        let mut sos: SosFilter = SosFilter::from_zpk(&zpk).unwrap();
+        let mut input = vec![0.0; 50];
+        input[0] = 1.0;
+        let mut expected = vec![0.0; 50];
+        sos.process_buffer(&input, &mut expected).unwrap();
+        let impulse = sos.impulse_response(50);
+        let from_zpk = zpk.impulse_response(50);
+        for i in 0..50 {
+            assert_approx_eq!(impulse[i], expected[i], 1e-6);
+            assert_approx_eq!(from_zpk[i], expected[i], 1e-6);
+        }
+        let step = sos.step_response(200);
+        assert_approx_eq!(step[199], 1.0, 1e-4);
+    }
+
+    #[test]
+    fn test_group_delay() {
+        // Group delay is the derivative of the phase
+        let filter = iir::butterworth(3, Band::Lowpass(1_000.0), SAMPLE_RATE).unwrap();
+        let df = 1.0;
+        let phase = filter.phase(&[499.0, 500.0, 501.0], SAMPLE_RATE);
+        let expected = -(phase[2] - phase[0]) / (2.0 * omega(df, SAMPLE_RATE) as f32);
+        let delay = filter.group_delay(&[500.0], SAMPLE_RATE);
+        assert_approx_eq!(delay[0], expected, 1e-2);
+    }
+
+    #[test]
+    fn test_poles_and_zeros() {
+        // Poles at 0.5 ± 0.5j, double zero at -1
+        let filter: BiquadFilter = BiquadFilter::new(&[1.0, 2.0, 1.0], &[1.0, -1.0, 0.5]).unwrap();
+        let mut poles = filter.poles();
+        poles.sort_by(|a, b| a.im.partial_cmp(&b.im).unwrap());
+        assert_approx_eq!(poles[0].re, 0.5, 1e-7);
+        assert_approx_eq!(poles[0].im, -0.5, 1e-7);
+        assert_approx_eq!(poles[1].im, 0.5, 1e-7);
+        assert_eq!(filter.zeros(), vec![Complex64::new(-1.0, 0.0); 2]);
+        assert!(filter.is_stable());
+
+        let filter: BiquadFilter = BiquadFilter::new(&[1.0, 0.0, 0.0], &[1.0, -2.5, 1.0]).unwrap();
+        assert!(!filter.is_stable());
+    }
+
+    #[test]
+    fn test_sos_poles() {
+        let zpk = iir::digital(Prototype::Elliptic { ripple: 1.0, attenuation: 40.0 }, 5, Band::Lowpass(1_000.0), SAMPLE_RATE).unwrap();
+        let sos: SosFilter<f64> = SosFilter::from_zpk(&zpk).unwrap();
+        let poles = sos.poles();
+        assert_eq!(poles.len(), 5);
+        assert_eq!(sos.zeros().len(), 5);
+        for p in zpk.poles.iter() {
+            assert!(poles.iter().any(|q| (p - q).norm() < 1e-6));
+        }
+        assert!(sos.is_stable());
+        // FIR filter has only poles at 0
+        let fir: FirFilter = FirFilter::new(&[1.0, 0.5]).unwrap();
+        assert_eq!(fir.zeros(), vec![Complex64::new(-0.5, 0.0)]);
+        assert_eq!(fir.poles(), vec![Complex64::new(0.0, 0.0)]);
+    }
+
+    #[test]
+    fn test_unwrap() {
+        let mut phase = vec![0.0, 3.0, -3.0, -0.5, 2.5, -3.5];
+        unwrap(&mut phase);
+        let two_pi = 2.0 * std::f32::consts::PI;
+        let expected = [0.0, 3.0, two_pi - 3.0, two_pi - 0.5, two_pi + 2.5, 2.0 * two_pi - 3.5];
+        for i in 0..phase.len() {
+            assert_approx_eq!(phase[i], expected[i], 1e-6);
+        }
+    }
+}
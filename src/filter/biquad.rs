@@ -1,82 +1,349 @@
 /// Basic implementations of common discrete filters
-use arraydeque::{ArrayDeque, Wrapping};
-use itertools::izip;
+use num_traits::{cast, Float, One, Zero};
+use crate::{Error, Result};
+use crate::filter::analysis::FilterResponse;
+use crate::runtime::node::ProcessNode;
+use crate::sample::Sample;
+
+
+/// Width of the cookbook filters
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Width {
+    /// Quality factor
+    Q(f32),
+    /// Bandwidth in octaves (between -3dB frequencies for bandpass and notch,
+    /// between midpoint (dBgain/2) gain frequencies for peaking EQ)
+    Bandwidth(f32),
+    /// Shelf slope. 1.0 is the steepest slope, for which the response is still monotonic
+    Slope(f32),
+}
+
+
+/// Structure used to compute the filter output
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Topology {
+    /// Direct form I. Keeps last 2 input and output samples
+    DirectForm1,
+    /// Transposed direct form II. Keeps 2 state variables
+    #[default]
+    TransposedDirectForm2,
+}
 
 
 /// A biquad filter (IIR)
+/// 
+/// Coefficients are normalized, so a0 = 1:
+/// 
+/// y[n] = b0*x[n] + b1*x[n-1] + b2*x[n-2] - a1*y[n-1] - a2*y[n-2]
+/// 
+/// Samples can be real or complex (f32, f64, Complex32, Complex64). Coefficients are always real.
 #[derive(Clone,Debug)]
-pub struct BiquadFilter {
-    x: ArrayDeque<f32, 3, Wrapping>,
-    y: ArrayDeque<f32, 3, Wrapping>,
-    b: [f32; 3],
-    a: [f32; 3]
+pub struct BiquadFilter<T: Sample = f32> {
+    b: [T::Real; 3],
+    // a1 and a2
+    a: [T::Real; 2],
+    topology: Topology,
+    // DF1: x[n-1], x[n-2], y[n-1], y[n-2]. DF2T: s1, s2
+    state: [T; 4],
 }
 
 
-impl BiquadFilter {
+impl<T: Sample> BiquadFilter<T> {
 
-    /// Returns a new biquad IIR filter. Failure if a/b not correct lengths
-    pub fn new(b: &[f32], a: &[f32]) -> BiquadFilter {
+    /// Returns a new biquad IIR filter in transposed direct form II. 
+    /// Failure if a/b not correct lengths
+    pub fn new(b: &[T::Real], a: &[T::Real]) -> Result<BiquadFilter<T>> {
+        let mut filter = BiquadFilter {
+            b: [T::Real::zero(); 3], 
+            a: [T::Real::zero(); 2],
+            topology: Topology::default(),
+            state: [T::default(); 4],
+        };
+        filter.set_coefficients(b, a)?;
+        Ok(filter)
+    }
+
+    /// Returns a new biquad IIR filter. Failure if a/b not correct lengths 
+    /// or the filter is unstable (has poles outside of the unit circle).
+    /// 
+    /// Example
+    /// 
+    /// ```
+    /// use dsp::filter::biquad::BiquadFilter;
+    /// 
+    /// assert!(BiquadFilter::<f32>::new_stable(&[1.0, 0.0, 0.0], &[1.0, -0.5, 0.0]).is_ok());
+    /// assert!(BiquadFilter::<f32>::new_stable(&[1.0, 0.0, 0.0], &[1.0, -2.0, 0.0]).is_err());
+    /// ```
+    pub fn new_stable(b: &[T::Real], a: &[T::Real]) -> Result<BiquadFilter<T>> {
+        let filter = BiquadFilter::new(b, a)?;
+        check_stable(&filter)?;
+        Ok(filter)
+    }
+
+    /// Use given topology. Filter state is converted to the new topology.
+    /// 
+    /// Example
+    /// 
+    /// ```
+    /// use dsp::filter::biquad::{BiquadFilter, Topology, Width};
+    /// 
+    /// let filter: BiquadFilter = BiquadFilter::lowpass(1_000.0, Width::Q(0.7), 44_100).unwrap()
+    ///     .with_topology(Topology::DirectForm1);
+    /// assert_eq!(filter.topology(), Topology::DirectForm1);
+    /// ```
+    pub fn with_topology(mut self, topology: Topology) -> BiquadFilter<T> {
+        self.set_topology(topology);
+        self
+    }
+
+    /// Change topology while processing the signal. Filter state is converted,
+    /// so the output continues as if the topology didn't change.
+    pub fn set_topology(&mut self, topology: Topology) {
+        if topology != self.topology {
+            self.state = match topology {
+                Topology::DirectForm1 => self.df1_history(),
+                Topology::TransposedDirectForm2 => self.df2t_state(self.state),
+            };
+            self.topology = topology;
+        }
+    }
+
+    /// Filter topology
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Clear filter state
+    pub fn reset(&mut self) {
+        self.state = [T::default(); 4];
+    }
+
+    /// Replace filter coefficients, so the parameters can be changed while processing the signal.
+    /// DF1 keeps the last input and output samples. DF2T state is rebuilt from the equivalent
+    /// input and output samples, because its state variables depend on the coefficients.
+    /// 
+    /// Example
+    /// 
+    /// ```
+    /// use dsp::filter::biquad::{BiquadFilter, Width};
+    /// 
+    /// let mut filter: BiquadFilter = BiquadFilter::peaking(1_000.0, Width::Q(1.0), 6.0, 44_100).unwrap();
+    /// // Sweep the center frequency
+    /// let (b, a) = BiquadFilter::<f32>::peaking(1_100.0, Width::Q(1.0), 6.0, 44_100).unwrap().coefficients();
+    /// filter.set_coefficients(&b, &a).unwrap();
+    /// ```
+    pub fn set_coefficients(&mut self, b: &[T::Real], a: &[T::Real]) -> Result<()> {
 
         // Sanity check
-        assert_eq!(b.len(), 3);
-        assert_eq!(a.len(), 3);
-        assert_ne!(a[0], 0.0); // a0 of 0 results in divide by 0
-
-        // Initialize sample histories
-        let mut x: ArrayDeque<f32, 3, Wrapping> = ArrayDeque::new();
-        let mut y: ArrayDeque<f32, 3, Wrapping> = ArrayDeque::new();
-        for _ in 0..3 {
-            x.push_front(0.0);
-            y.push_front(0.0);
+        if b.len() != 3 || a.len() != 3 {
+            return Err(Error::Config("Biquad filter requires 3 b and 3 a coefficients".to_string()));
+        }
+        if a[0].is_zero() {
+            // a0 of 0 results in divide by 0
+            return Err(Error::Config("Coefficient a0 can't be 0".to_string()));
         }
 
-        // Clone the b coefficients from passed in slice
-        let mut b_arr: [f32; 3] = [0.0; 3];
-        b_arr.clone_from_slice(b);
+        let history = match self.topology {
+            Topology::DirectForm1 => self.state,
+            Topology::TransposedDirectForm2 => self.df1_history(),
+        };
 
-        // Clone the a coefficients, inverting a[1..] by
-        // the definition of an IIR filter
-        let mut neg_a_arr: [f32; 3] = [0.0; 3];
-        neg_a_arr[0] = a[0];
-        for i in 1..3 {
-            neg_a_arr[i] = -a[i];
+        // Normalize, so there is no division when processing samples
+        self.b = [b[0] / a[0], b[1] / a[0], b[2] / a[0]];
+        self.a = [a[1] / a[0], a[2] / a[0]];
+        if self.topology == Topology::TransposedDirectForm2 {
+            self.state = self.df2t_state(history);
         }
+        Ok(())
+    }
+
+    /// DF2T state which gives the same output as DF1 with history [x1, x2, y1, y2]
+    fn df2t_state(&self, history: [T; 4]) -> [T; 4] {
+        let [_, b1, b2] = self.b;
+        let [a1, a2] = self.a;
+        let [x1, x2, y1, y2] = history;
+        [x1 * b1 - y1 * a1 + x2 * b2 - y2 * a2, x1 * b2 - y1 * a2, T::default(), T::default()]
+    }
 
-        // New filter with x/y initalized to same length as a/b
-        BiquadFilter {
-            x, y,
-            b: b_arr, a: neg_a_arr
+    /// DF1 history [x1, x2, y1, y2] which gives the same output as the current DF2T state.
+    /// Only the input or the output samples are needed, whichever gives the larger divisor.
+    fn df1_history(&self) -> [T; 4] {
+        let [_, b1, b2] = self.b;
+        let [a1, a2] = self.a;
+        let [s1, s2, _, _] = self.state;
+        let zero = T::default();
+        if !a2.is_zero() && a2.abs() >= b2.abs() {
+            let y1 = s2 * -a2.recip();
+            [zero, zero, y1, (s1 + y1 * a1) * -a2.recip()]
+        } else if !b2.is_zero() {
+            let x1 = s2 * b2.recip();
+            [x1, (s1 - x1 * b1) * b2.recip(), zero, zero]
+        } else if !a1.is_zero() && a1.abs() >= b1.abs() {
+            [zero, zero, s1 * -a1.recip(), zero]
+        } else if !b1.is_zero() {
+            [s1 * b1.recip(), zero, zero, zero]
+        } else {
+            [zero; 4]
         }
     }
 
+    /// Filter coefficients (b, a) normalized to a0 = 1
+    pub fn coefficients(&self) -> ([T::Real; 3], [T::Real; 3]) {
+        (self.b, [T::Real::one(), self.a[0], self.a[1]])
+    }
+
+    /// Lowpass filter from the Audio EQ Cookbook
+    pub fn lowpass(freq: f32, width: Width, sample_rate: usize) -> Result<BiquadFilter<T>> {
+        cookbook(freq, width, 0.0, sample_rate, |cos, _, alpha, _| {
+            ([(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+        })
+    }
+
+    /// Highpass filter from the Audio EQ Cookbook
+    pub fn highpass(freq: f32, width: Width, sample_rate: usize) -> Result<BiquadFilter<T>> {
+        cookbook(freq, width, 0.0, sample_rate, |cos, _, alpha, _| {
+            ([(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+        })
+    }
+
+    /// Bandpass filter with constant skirt gain (peak gain = Q) from the Audio EQ Cookbook
+    pub fn bandpass_skirt(freq: f32, width: Width, sample_rate: usize) -> Result<BiquadFilter<T>> {
+        cookbook(freq, width, 0.0, sample_rate, |cos, sin, alpha, _| {
+            ([sin / 2.0, 0.0, -sin / 2.0], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+        })
+    }
+
+    /// Bandpass filter with constant 0dB peak gain from the Audio EQ Cookbook
+    pub fn bandpass_peak(freq: f32, width: Width, sample_rate: usize) -> Result<BiquadFilter<T>> {
+        cookbook(freq, width, 0.0, sample_rate, |cos, _, alpha, _| {
+            ([alpha, 0.0, -alpha], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+        })
+    }
+
+    /// Notch filter from the Audio EQ Cookbook
+    pub fn notch(freq: f32, width: Width, sample_rate: usize) -> Result<BiquadFilter<T>> {
+        cookbook(freq, width, 0.0, sample_rate, |cos, _, alpha, _| {
+            ([1.0, -2.0 * cos, 1.0], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+        })
+    }
+
+    /// Allpass filter from the Audio EQ Cookbook
+    pub fn allpass(freq: f32, width: Width, sample_rate: usize) -> Result<BiquadFilter<T>> {
+        cookbook(freq, width, 0.0, sample_rate, |cos, _, alpha, _| {
+            ([1.0 - alpha, -2.0 * cos, 1.0 + alpha], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+        })
+    }
+
+    /// Peaking EQ from the Audio EQ Cookbook. Gain at the center frequency is `gain_db`
+    pub fn peaking(freq: f32, width: Width, gain_db: f32, sample_rate: usize) -> Result<BiquadFilter<T>> {
+        cookbook(freq, width, gain_db, sample_rate, |cos, _, alpha, a| {
+            ([1.0 + alpha * a, -2.0 * cos, 1.0 - alpha * a], [1.0 + alpha / a, -2.0 * cos, 1.0 - alpha / a])
+        })
+    }
+
+    /// Low shelf from the Audio EQ Cookbook. Gain below the shelf frequency is `gain_db`
+    pub fn low_shelf(freq: f32, width: Width, gain_db: f32, sample_rate: usize) -> Result<BiquadFilter<T>> {
+        cookbook(freq, width, gain_db, sample_rate, |cos, _, alpha, a| {
+            let beta = 2.0 * a.sqrt() * alpha;
+            ([a * ((a + 1.0) - (a - 1.0) * cos + beta),
+              2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+              a * ((a + 1.0) - (a - 1.0) * cos - beta)],
+             [(a + 1.0) + (a - 1.0) * cos + beta,
+              -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+              (a + 1.0) + (a - 1.0) * cos - beta])
+        })
+    }
+
+    /// High shelf from the Audio EQ Cookbook. Gain above the shelf frequency is `gain_db`
+    pub fn high_shelf(freq: f32, width: Width, gain_db: f32, sample_rate: usize) -> Result<BiquadFilter<T>> {
+        cookbook(freq, width, gain_db, sample_rate, |cos, _, alpha, a| {
+            let beta = 2.0 * a.sqrt() * alpha;
+            ([a * ((a + 1.0) + (a - 1.0) * cos + beta),
+              -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+              a * ((a + 1.0) + (a - 1.0) * cos - beta)],
+             [(a + 1.0) - (a - 1.0) * cos + beta,
+              2.0 * ((a - 1.0) - (a + 1.0) * cos),
+              (a + 1.0) - (a - 1.0) * cos - beta])
+        })
+    }
+
     /// Process one sample of the input signal and returns one sample of the
     /// output signal.
-    fn process_one(&mut self, in_samp: f32) -> f32 {
+    #[inline]
+    pub fn process_one(&mut self, x: T) -> T {
+        match self.topology {
+            Topology::DirectForm1 => self.process_df1(x),
+            Topology::TransposedDirectForm2 => self.process_df2t(x),
+        }
+    }
 
-        // Shift in old values
-        self.x.pop_back();
-        self.x.push_front(in_samp.clone());
-        self.y.pop_back();
+    #[inline(always)]
+    fn process_df1(&mut self, x: T) -> T {
+        let [b0, b1, b2] = self.b;
+        let [a1, a2] = self.a;
+        let s = &mut self.state;
+        let y = x * b0 + s[0] * b1 + s[1] * b2 - s[2] * a1 - s[3] * a2;
+        *s = [x, s[0], y, s[2]];
+        y
+    }
 
-        // Compute the filter result
-        let mut sum = 0.0;
-        for (xi, bi) in izip!(self.x.iter(), self.b.iter()) {
-            sum += *xi * *bi;
-        }
-        for (yi, ai) in izip!(self.y.iter(), self.a[1..].iter()) {
-            sum += *yi * *ai;
-        }
-        sum /= self.a[0];
+    #[inline(always)]
+    fn process_df2t(&mut self, x: T) -> T {
+        let [b0, b1, b2] = self.b;
+        let [a1, a2] = self.a;
+        let s = &mut self.state;
+        let y = x * b0 + s[0];
+        s[0] = x * b1 - y * a1 + s[1];
+        s[1] = x * b2 - y * a2;
+        y
+    }
+}
 
-        // Update y and return the result
-        self.y.push_front(sum);
-        sum
+/// Return error if any of the filter poles is not inside the unit circle
+pub(crate) fn check_stable<F: FilterResponse>(filter: &F) -> Result<()> {
+    match filter.poles().iter().map(|p| p.norm()).find(|r| *r >= 1.0) {
+        Some(r) => Err(Error::Config(format!("Filter is unstable. Pole magnitude {} is not less then 1", r))),
+        None => Ok(()),
     }
+}
+
+/// Create filter from the cookbook formula.
+/// Formula gets cos(w0), sin(w0), alpha and A, and returns (b, a) coefficients.
+/// https://www.w3.org/TR/audio-eq-cookbook/
+fn cookbook<T, F>(freq: f32, width: Width, gain_db: f32, sample_rate: usize, formula: F) -> Result<BiquadFilter<T>>
+where
+    T: Sample,
+    F: Fn(f64, f64, f64, f64) -> ([f64; 3], [f64; 3])
+{
+    let fs = sample_rate as f64;
+    if freq <= 0.0 || freq as f64 >= fs / 2.0 {
+        return Err(Error::Config(format!("Frequency {} should be between 0 and {}", freq, fs / 2.0)));
+    }
+    let a = 10f64.powf(gain_db as f64 / 40.0);
+    let w0 = 2.0 * std::f64::consts::PI * freq as f64 / fs;
+    let (sin, cos) = w0.sin_cos();
+    let alpha = match width {
+        Width::Q(q) if q > 0.0 => sin / (2.0 * q as f64),
+        Width::Bandwidth(bw) if bw > 0.0 => sin * (std::f64::consts::LN_2 / 2.0 * bw as f64 * w0 / sin).sinh(),
+        Width::Slope(s) if s > 0.0 => sin / 2.0 * ((a + 1.0 / a) * (1.0 / s as f64 - 1.0) + 2.0).sqrt(),
+        _ => return Err(Error::Config(format!("Invalid filter width {:?}", width))),
+    };
+    let (b, a) = formula(cos, sin, alpha, a);
+    let b: Vec<T::Real> = b.iter().map(|v| cast(v / a[0]).unwrap()).collect();
+    let a: Vec<T::Real> = a.iter().map(|v| cast(v / a[0]).unwrap()).collect();
+    BiquadFilter::new(&b, &a)
+}
 
-    pub fn process_buffer(&mut self, input_buffer: &[f32], output_buffer: &mut [f32]) {
+impl<T: Sample> ProcessNode<T, T> for BiquadFilter<T> {
+    fn process_buffer(&mut self, input_buffer: &[T], output_buffer: &mut [T]) -> Result<usize> {
         let size = std::cmp::min(input_buffer.len(), output_buffer.len());
-        (0..size).for_each(|i| output_buffer[i] = self.process_one(input_buffer[i]));
+        let samples = input_buffer.iter().zip(output_buffer.iter_mut());
+        // Select topology once per buffer
+        match self.topology {
+            Topology::DirectForm1 => samples.for_each(|(x, y)| *y = self.process_df1(*x)),
+            Topology::TransposedDirectForm2 => samples.for_each(|(x, y)| *y = self.process_df2t(*x)),
+        }
+        Ok(size)
     }
 }
 
@@ -87,7 +354,9 @@ impl BiquadFilter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::f32::consts::FRAC_1_SQRT_2;
     use assert_approx_eq::assert_approx_eq;
+    use num_complex::Complex32;
 
     #[test]
     fn test_biquad_bilinear_rc() {
@@ -111,7 +380,7 @@ mod tests {
         // https://en.wikipedia.org/wiki/Bilinear_transform#Example
         let b = [1.0, 1.0, 0.0];
         let a = [1.0+(2.0*rc/t_samp), 1.0-(2.0*rc/t_samp), 0.0];
-        let mut biquad_rc = BiquadFilter::new(&b, &a);
+        let mut biquad_rc = BiquadFilter::new(&b, &a).unwrap();
         let dig_unit_step = vec![1.0; 50];
         let mut digital_response = vec![0.0; 50];
         let _ = biquad_rc.process_buffer(&dig_unit_step, &mut digital_response);
@@ -123,4 +392,167 @@ mod tests {
             assert_approx_eq!(analog_response[i], digital_response[i], 0.05);
         }
     }
+
+    /// Filter gain in dB at the given frequency
+    fn gain_db(filter: &BiquadFilter, freq: f32, sample_rate: usize) -> f32 {
+        let (b, a) = filter.coefficients();
+        let z = Complex32::from_polar(1.0, -2.0 * std::f32::consts::PI * freq / sample_rate as f32);
+        let h = (b[0] + z * b[1] + z * z * b[2]) / (a[0] + z * a[1] + z * z * a[2]);
+        20.0 * h.norm().log10()
+    }
+
+    #[test]
+    fn test_cookbook() {
+        let fs = 48_000;
+        let f = BiquadFilter::lowpass(1_000.0, Width::Q(FRAC_1_SQRT_2), fs).unwrap();
+        assert_approx_eq!(gain_db(&f, 0.0, fs), 0.0, 1e-4);
+        assert_approx_eq!(gain_db(&f, 1_000.0, fs), -3.0103, 1e-3);
+        let f = BiquadFilter::highpass(1_000.0, Width::Q(FRAC_1_SQRT_2), fs).unwrap();
+        assert_approx_eq!(gain_db(&f, 24_000.0, fs), 0.0, 1e-4);
+        assert_approx_eq!(gain_db(&f, 1_000.0, fs), -3.0103, 1e-3);
+        let f = BiquadFilter::bandpass_peak(1_000.0, Width::Bandwidth(1.0), fs).unwrap();
+        assert_approx_eq!(gain_db(&f, 1_000.0, fs), 0.0, 1e-4);
+        let f = BiquadFilter::bandpass_skirt(1_000.0, Width::Q(4.0), fs).unwrap();
+        assert_approx_eq!(gain_db(&f, 1_000.0, fs), 20.0 * 4f32.log10(), 1e-3);
+        let f = BiquadFilter::notch(1_000.0, Width::Q(2.0), fs).unwrap();
+        assert!(gain_db(&f, 1_000.0, fs) < -60.0);
+        assert_approx_eq!(gain_db(&f, 0.0, fs), 0.0, 1e-4);
+        let f = BiquadFilter::allpass(1_000.0, Width::Q(2.0), fs).unwrap();
+        for freq in [0.0, 500.0, 1_000.0, 10_000.0] {
+            assert_approx_eq!(gain_db(&f, freq, fs), 0.0, 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_cookbook_gain() {
+        let fs = 48_000;
+        let f = BiquadFilter::peaking(1_000.0, Width::Q(1.0), 6.0, fs).unwrap();
+        assert_approx_eq!(gain_db(&f, 1_000.0, fs), 6.0, 1e-3);
+        assert_approx_eq!(gain_db(&f, 0.0, fs), 0.0, 1e-3);
+        let f = BiquadFilter::low_shelf(1_000.0, Width::Slope(1.0), -12.0, fs).unwrap();
+        assert_approx_eq!(gain_db(&f, 0.0, fs), -12.0, 1e-3);
+        assert_approx_eq!(gain_db(&f, 1_000.0, fs), -6.0, 1e-3);
+        assert_approx_eq!(gain_db(&f, 24_000.0, fs), 0.0, 1e-3);
+        let f = BiquadFilter::high_shelf(1_000.0, Width::Slope(1.0), 12.0, fs).unwrap();
+        assert_approx_eq!(gain_db(&f, 0.0, fs), 0.0, 1e-3);
+        assert_approx_eq!(gain_db(&f, 24_000.0, fs), 12.0, 1e-3);
+    }
+
+    #[test]
+    fn test_invalid_cookbook_params() {
+        assert!(BiquadFilter::<f32>::lowpass(30_000.0, Width::Q(1.0), 48_000).is_err());
+        assert!(BiquadFilter::<f32>::peaking(1_000.0, Width::Q(0.0), 3.0, 48_000).is_err());
+    }
+
+    #[test]
+    fn test_set_coefficients_keeps_state() {
+        let input: Vec<f32> = (0..20).map(|i| (i as f32 * 0.7).sin()).collect();
+        let mut expected = vec![0.0; 20];
+        let mut filter = BiquadFilter::lowpass(1_000.0, Width::Q(1.0), 8_000).unwrap();
+        filter.clone().process_buffer(&input, &mut expected).unwrap();
+
+        let mut output = vec![0.0; 20];
+        filter.process_buffer(&input[..10], &mut output[..10]).unwrap();
+        let (b, a) = filter.coefficients();
+        filter.set_coefficients(&b, &a).unwrap();
+        filter.process_buffer(&input[10..], &mut output[10..]).unwrap();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_topologies() {
+        let input: Vec<f32> = (0..100).map(|i| (i as f32 * 0.3).sin() + 0.5).collect();
+        let mut filter = BiquadFilter::peaking(1_000.0, Width::Q(2.0), 9.0, 8_000).unwrap();
+        let mut expected = vec![0.0; 100];
+        let mut output = vec![0.0; 100];
+        filter.clone().with_topology(Topology::DirectForm1).process_buffer(&input, &mut expected).unwrap();
+        filter.process_buffer(&input, &mut output).unwrap();
+        for i in 0..100 {
+            assert_approx_eq!(output[i], expected[i], 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_coefficient_sweep() {
+        // Steady state 200Hz tone while the peaking EQ is swept from 100Hz to 3kHz and from +12 to -12dB
+        let fs = 8_000;
+        let input: Vec<f32> = (0..4_000).map(|i| (2.0 * std::f32::consts::PI * 200.0 * i as f32 / fs as f32).sin()).collect();
+        let max_step = 2.0 * (std::f32::consts::PI * 200.0 / fs as f32).sin();
+        for topology in [Topology::DirectForm1, Topology::TransposedDirectForm2] {
+            let mut filter = BiquadFilter::peaking(100.0, Width::Q(2.0), 12.0, fs).unwrap()
+                .with_topology(topology);
+            let mut output = vec![0.0; input.len()];
+            filter.process_buffer(&input[..2_000], &mut output[..2_000]).unwrap();
+            for (k, chunk) in input[2_000..].chunks(20).enumerate() {
+                let t = k as f32 / 100.0;
+                let (b, a) = BiquadFilter::<f32>::peaking(100.0 + 2_900.0 * t, Width::Q(2.0), 12.0 - 24.0 * t, fs)
+                    .unwrap().coefficients();
+                filter.set_coefficients(&b, &a).unwrap();
+                let start = 2_000 + 20 * k;
+                filter.process_buffer(chunk, &mut output[start..start + chunk.len()]).unwrap();
+            }
+            // Output can change only as fast as the signal with the largest gain
+            let jump = output[1_000..].windows(2).map(|w| (w[1] - w[0]).abs()).fold(0.0, f32::max);
+            assert!(jump < 4.0 * max_step, "{:?} jump {}", topology, jump);
+        }
+    }
+
+    #[test]
+    fn test_complex_samples() {
+        // Filtering complex signal is the same as filtering real and imaginary parts
+        let input: Vec<Complex32> = (0..50).map(|i| Complex32::new((i as f32 * 0.3).sin(), (i as f32 * 0.7).cos())).collect();
+        let mut filter: BiquadFilter<Complex32> = BiquadFilter::lowpass(1_000.0, Width::Q(0.7), 8_000).unwrap();
+        let mut output = vec![Complex32::new(0.0, 0.0); 50];
+        filter.process_buffer(&input, &mut output).unwrap();
+
+        let re: Vec<f32> = input.iter().map(|x| x.re).collect();
+        let im: Vec<f32> = input.iter().map(|x| x.im).collect();
+        let mut re_out = vec![0.0; 50];
+        let mut im_out = vec![0.0; 50];
+        BiquadFilter::lowpass(1_000.0, Width::Q(0.7), 8_000).unwrap().process_buffer(&re, &mut re_out).unwrap();
+        BiquadFilter::lowpass(1_000.0, Width::Q(0.7), 8_000).unwrap().process_buffer(&im, &mut im_out).unwrap();
+        for i in 0..50 {
+            assert_approx_eq!(output[i].re, re_out[i], 1e-6);
+            assert_approx_eq!(output[i].im, im_out[i], 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_switch_topology() {
+        let input: Vec<f32> = (0..200).map(|i| (i as f32 * 0.3).sin() + 0.5).collect();
+        let mut filter = BiquadFilter::lowpass(500.0, Width::Q(0.7), 8_000).unwrap()
+            .with_topology(Topology::DirectForm1);
+        let mut expected = vec![0.0; 200];
+        filter.clone().process_buffer(&input, &mut expected).unwrap();
+
+        let mut output = vec![0.0; 200];
+        for (k, (inp, out)) in input.chunks(50).zip(output.chunks_mut(50)).enumerate() {
+            filter.set_topology(if k % 2 == 0 { Topology::TransposedDirectForm2 } else { Topology::DirectForm1 });
+            filter.process_buffer(inp, out).unwrap();
+        }
+        for (y, e) in output.iter().zip(expected.iter()) {
+            assert_approx_eq!(*y, *e, 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_f64_samples() {
+        let mut filter: BiquadFilter<f64> = BiquadFilter::lowpass(10.0, Width::Q(0.7), 48_000).unwrap();
+        let input = vec![1.0; 20_000];
+        let mut output = vec![0.0; 20_000];
+        filter.process_buffer(&input, &mut output).unwrap();
+        assert_approx_eq!(output[19_999], 1.0, 1e-6);
+    }
+
+    #[test]
+    fn test_normalized_coefficients() {
+        let filter: BiquadFilter = BiquadFilter::new(&[2.0, 4.0, 2.0], &[2.0, 1.0, 0.5]).unwrap();
+        assert_eq!(filter.coefficients(), ([1.0, 2.0, 1.0], [1.0, 0.5, 0.25]));
+    }
+
+    #[test]
+    fn test_invalid_coefficients() {
+        assert!(matches!(BiquadFilter::<f32>::new(&[1.0, 1.0], &[1.0, 0.0, 0.0]), Err(Error::Config(_))));
+        assert!(matches!(BiquadFilter::<f32>::new(&[1.0, 1.0, 0.0], &[0.0, 1.0, 0.0]), Err(Error::Config(_))));
+    }
 }
@@ -0,0 +1,415 @@
+//! Fast convolution with FFT
+//!
+//! Direct form FIR filter needs N multiplications per sample, which is too slow for
+//! long impulse responses. These blocks compute the same output in the frequency domain:
+//!
+//!   * FftConvolver - single FFT block with overlap-add or overlap-save method
+//!   * PartitionedConvolver - impulse response split into uniform partitions.
+//!     Cost and latency depends on the partition size and not on the impulse response length.
+//!
+//! The input can be processed in buffers of any size and the output is not delayed,
+//! so both blocks can replace `FirFilter`. Buffers which are multiples of the block size
+//! are processed most efficiently.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use num_complex::Complex;
+use num_traits::{cast, Float, Zero};
+use rustfft::{Fft, FftNum, FftPlanner};
+use crate::{Error, Result};
+use crate::runtime::node::ProcessNode;
+use crate::sample::Sample;
+
+
+/// Block convolution method
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Method {
+    /// Convolve zero padded blocks and add the overlapping tails
+    #[default]
+    OverlapAdd,
+    /// Convolve overlapping input blocks and discard the aliased samples
+    OverlapSave,
+}
+
+
+/// FIR filter implemented with FFT convolution
+///
+/// Example
+///
+/// ```
+/// use dsp::runtime::node::ProcessNode;
+/// use dsp::filter::convolution::{FftConvolver, Method};
+///
+/// let mut conv = FftConvolver::new(&[0.5, 0.5], 4, Method::OverlapSave).unwrap();
+/// let input_buffer = vec![2.0, 4.0, 6.0];
+/// let mut output_buffer = vec![0.0; 3];
+/// conv.process_buffer(&input_buffer, &mut output_buffer).unwrap();
+///
+/// assert!((output_buffer[2] - 5.0f32).abs() < 1e-5);
+/// ```
+pub struct FftConvolver<T: Sample = f32> {
+    method: Method,
+    block_size: usize,
+    taps_len: usize,
+    // Spectrum of the taps scaled by 1/N
+    spectrum: Vec<Complex<T::Real>>,
+    fft: Arc<dyn Fft<T::Real>>,
+    ifft: Arc<dyn Fft<T::Real>>,
+    buffer: Vec<Complex<T::Real>>,
+    // Overlap-add: tail of the previous blocks. Overlap-save: last N input samples
+    state: Vec<Complex<T::Real>>,
+}
+
+impl<T: Sample> FftConvolver<T> {
+    /// Create new convolver
+    ///   * taps - impulse response
+    ///   * block_size - max number of samples processed with single FFT.
+    ///     FFT size is the next power of 2 of block_size + taps.len() - 1
+    ///   * method - overlap-add or overlap-save
+    pub fn new(taps: &[T::Real], block_size: usize, method: Method) -> Result<FftConvolver<T>> {
+        check_params(taps.len(), block_size)?;
+        let fft_size = (block_size + taps.len() - 1).next_power_of_two();
+        let mut planner = FftPlanner::new();
+        let fft = planner.plan_fft_forward(fft_size);
+        let ifft = planner.plan_fft_inverse(fft_size);
+        let spectrum = taps_spectrum(taps, fft_size, fft.as_ref());
+        let state_size = match method {
+            Method::OverlapAdd => taps.len() - 1,
+            Method::OverlapSave => fft_size,
+        };
+        Ok(FftConvolver {
+            method,
+            block_size,
+            taps_len: taps.len(),
+            spectrum,
+            fft,
+            ifft,
+            buffer: vec![Complex::zero(); fft_size],
+            state: vec![Complex::zero(); state_size],
+        })
+    }
+
+    /// FFT size used by this convolver
+    pub fn fft_size(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Clear filter state
+    pub fn reset(&mut self) {
+        self.state.fill(Complex::zero());
+    }
+
+    /// Convolve block which is not longer then block size
+    fn process_block(&mut self, input: &[T], output: &mut [T]) {
+        let n = input.len();
+        let fft_size = self.buffer.len();
+        match self.method {
+            Method::OverlapAdd => {
+                for (b, x) in self.buffer.iter_mut().zip(input) {
+                    *b = x.to_complex();
+                }
+                self.buffer[n..].fill(Complex::zero());
+            }
+            Method::OverlapSave => {
+                self.state.copy_within(n.., 0);
+                for (s, x) in self.state[fft_size - n..].iter_mut().zip(input) {
+                    *s = x.to_complex();
+                }
+                self.buffer.copy_from_slice(&self.state);
+            }
+        }
+        self.fft.process(&mut self.buffer);
+        for (b, h) in self.buffer.iter_mut().zip(&self.spectrum) {
+            *b = *b * *h;
+        }
+        self.ifft.process(&mut self.buffer);
+
+        match self.method {
+            Method::OverlapAdd => {
+                let tail_len = self.taps_len - 1;
+                for (i, y) in output[..n].iter_mut().enumerate() {
+                    let tail = if i < tail_len { self.state[i] } else { Complex::zero() };
+                    *y = T::from_complex(self.buffer[i] + tail);
+                }
+                for i in 0..tail_len {
+                    let prev = if i + n < tail_len { self.state[i + n] } else { Complex::zero() };
+                    self.state[i] = prev + self.buffer[i + n];
+                }
+            }
+            Method::OverlapSave => {
+                for (y, b) in output[..n].iter_mut().zip(&self.buffer[fft_size - n..]) {
+                    *y = T::from_complex(*b);
+                }
+            }
+        }
+    }
+}
+
+impl<T: Sample> ProcessNode<T, T> for FftConvolver<T> {
+    fn process_buffer(&mut self, input_buffer: &[T], output_buffer: &mut [T]) -> Result<usize> {
+        let n = usize::min(input_buffer.len(), output_buffer.len());
+        let mut pos = 0;
+        while pos < n {
+            let end = usize::min(pos + self.block_size, n);
+            self.process_block(&input_buffer[pos..end], &mut output_buffer[pos..end]);
+            pos = end;
+        }
+        Ok(n)
+    }
+}
+
+
+/// Uniformly partitioned overlap-save convolution for very long impulse responses.
+/// Impulse response is split into partitions of the block size and the spectra of the past
+/// input blocks are kept in the frequency domain delay line.
+///
+/// Example
+///
+/// ```
+/// use dsp::runtime::node::ProcessNode;
+/// use dsp::filter::convolution::PartitionedConvolver;
+///
+/// // 1 second long decaying reverb tail at 8kHz
+/// let taps: Vec<f32> = (0..8_000).map(|i| (-(i as f32) / 1_000.0).exp()).collect();
+/// let mut conv = PartitionedConvolver::new(&taps, 64).unwrap();
+/// let input_buffer = vec![1.0; 100];
+/// let mut output_buffer = vec![0.0; 100];
+/// conv.process_buffer(&input_buffer, &mut output_buffer).unwrap();
+///
+/// assert!((output_buffer[1] - 1.999f32).abs() < 1e-3);
+/// ```
+pub struct PartitionedConvolver<T: Sample = f32> {
+    block_size: usize,
+    // Spectra of the partitions scaled by 1/N
+    partitions: Vec<Vec<Complex<T::Real>>>,
+    fft: Arc<dyn Fft<T::Real>>,
+    ifft: Arc<dyn Fft<T::Real>>,
+    // Previous and current input block
+    history: Vec<Complex<T::Real>>,
+    // Number of samples in the current block
+    fill: usize,
+    // Spectra of the previous blocks. The newest first
+    delay_line: VecDeque<Vec<Complex<T::Real>>>,
+    // Contribution of the previous blocks to the current block output
+    past: Vec<Complex<T::Real>>,
+    // Spectrum of the current input block
+    current: Vec<Complex<T::Real>>,
+    buffer: Vec<Complex<T::Real>>,
+}
+
+impl<T: Sample> PartitionedConvolver<T> {
+    /// Create new convolver
+    ///   * taps - impulse response
+    ///   * block_size - partition size. FFT size is 2 * block_size.
+    pub fn new(taps: &[T::Real], block_size: usize) -> Result<PartitionedConvolver<T>> {
+        check_params(taps.len(), block_size)?;
+        let fft_size = 2 * block_size;
+        let mut planner = FftPlanner::new();
+        let fft = planner.plan_fft_forward(fft_size);
+        let ifft = planner.plan_fft_inverse(fft_size);
+        let partitions: Vec<Vec<Complex<T::Real>>> = taps.chunks(block_size)
+            .map(|p| taps_spectrum(p, fft_size, fft.as_ref()))
+            .collect();
+        let delay_line = (1..partitions.len()).map(|_| vec![Complex::zero(); fft_size]).collect();
+        Ok(PartitionedConvolver {
+            block_size,
+            partitions,
+            fft,
+            ifft,
+            history: vec![Complex::zero(); fft_size],
+            fill: 0,
+            delay_line,
+            past: vec![Complex::zero(); fft_size],
+            current: vec![Complex::zero(); fft_size],
+            buffer: vec![Complex::zero(); fft_size],
+        })
+    }
+
+    /// Number of partitions
+    pub fn num_partitions(&self) -> usize {
+        self.partitions.len()
+    }
+
+    /// Clear filter state
+    pub fn reset(&mut self) {
+        self.history.fill(Complex::zero());
+        self.fill = 0;
+        for spectrum in self.delay_line.iter_mut() {
+            spectrum.fill(Complex::zero());
+        }
+        self.past.fill(Complex::zero());
+    }
+
+    /// Process samples which fit into the current block.
+    /// Samples which are not known yet are zeros, so they don't change the output.
+    fn process_block(&mut self, input: &[T], output: &mut [T]) {
+        let start = self.block_size + self.fill;
+        for (h, x) in self.history[start..].iter_mut().zip(input) {
+            *h = x.to_complex();
+        }
+        self.buffer.copy_from_slice(&self.history);
+        self.fft.process(&mut self.buffer);
+        self.current.copy_from_slice(&self.buffer);
+        for ((b, h), p) in self.buffer.iter_mut().zip(&self.partitions[0]).zip(&self.past) {
+            *b = *b * *h + *p;
+        }
+        self.ifft.process(&mut self.buffer);
+        for (y, b) in output.iter_mut().zip(&self.buffer[start..]) {
+            *y = T::from_complex(*b);
+        }
+
+        self.fill += input.len();
+        if self.fill == self.block_size {
+            self.next_block();
+        }
+    }
+
+    /// Move completed block into the delay line
+    fn next_block(&mut self) {
+        let n = self.block_size;
+        self.history.copy_within(n.., 0);
+        self.history[n..].fill(Complex::zero());
+        self.fill = 0;
+        if let Some(mut oldest) = self.delay_line.pop_back() {
+            oldest.copy_from_slice(&self.current);
+            self.delay_line.push_front(oldest);
+        }
+        self.past.fill(Complex::zero());
+        for (x, h) in self.delay_line.iter().zip(&self.partitions[1..]) {
+            for ((p, x), h) in self.past.iter_mut().zip(x).zip(h) {
+                *p = *p + *x * *h;
+            }
+        }
+    }
+}
+
+impl<T: Sample> ProcessNode<T, T> for PartitionedConvolver<T> {
+    fn process_buffer(&mut self, input_buffer: &[T], output_buffer: &mut [T]) -> Result<usize> {
+        let n = usize::min(input_buffer.len(), output_buffer.len());
+        let mut pos = 0;
+        while pos < n {
+            let end = usize::min(pos + self.block_size - self.fill, n);
+            self.process_block(&input_buffer[pos..end], &mut output_buffer[pos..end]);
+            pos = end;
+        }
+        Ok(n)
+    }
+}
+
+
+fn check_params(taps_len: usize, block_size: usize) -> Result<()> {
+    if taps_len == 0 {
+        Err(Error::Config("Convolution requires at least one tap".to_string()))
+    } else if block_size == 0 {
+        Err(Error::Config("Block size must be greater then 0".to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Spectrum of the zero padded taps, scaled by 1/N to normalize the inverse FFT
+fn taps_spectrum<R: Float + FftNum>(taps: &[R], fft_size: usize, fft: &dyn Fft<R>) -> Vec<Complex<R>> {
+    let scale: R = cast::<usize, R>(fft_size).unwrap().recip();
+    let mut spectrum = vec![Complex::zero(); fft_size];
+    for (s, t) in spectrum.iter_mut().zip(taps) {
+        *s = Complex::new(*t * scale, R::zero());
+    }
+    fft.process(&mut spectrum);
+    spectrum
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use num_complex::Complex32;
+    use crate::filter::fir::FirFilter;
+    use super::*;
+
+    fn taps(n: usize) -> Vec<f32> {
+        (0..n).map(|i| ((i * 7 % 13) as f32 - 6.0) / 10.0).collect()
+    }
+
+    fn signal(n: usize) -> Vec<f32> {
+        (0..n).map(|i| (i as f32 * 0.3).sin() + ((i * 5 % 11) as f32) / 11.0).collect()
+    }
+
+    /// Process signal in buffers of the given sizes
+    fn process<N: ProcessNode<T, T>, T: Sample>(node: &mut N, input: &[T], sizes: &[usize]) -> Vec<T> {
+        let mut output = vec![T::default(); input.len()];
+        let mut pos = 0;
+        for size in sizes.iter().cycle() {
+            if pos >= input.len() {
+                break;
+            }
+            let end = usize::min(pos + size, input.len());
+            assert_eq!(node.process_buffer(&input[pos..end], &mut output[pos..end]).unwrap(), end - pos);
+            pos = end;
+        }
+        output
+    }
+
+    #[test]
+    fn test_fft_convolver() {
+        let taps = taps(31);
+        let input = signal(500);
+        let mut fir: FirFilter = FirFilter::new(&taps).unwrap();
+        let expected = process(&mut fir, &input, &[500]);
+        for method in [Method::OverlapAdd, Method::OverlapSave] {
+            for block_size in [1, 16, 50] {
+                let mut conv: FftConvolver = FftConvolver::new(&taps, block_size, method).unwrap();
+                let output = process(&mut conv, &input, &[7, 64, 1, 100]);
+                for (y, e) in output.iter().zip(&expected) {
+                    assert_approx_eq!(y, e, 1e-4);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_complex_input() {
+        let taps = taps(20);
+        let input: Vec<Complex32> = signal(200).iter().zip(signal(201).iter().skip(1))
+            .map(|(re, im)| Complex32::new(*re, *im))
+            .collect();
+        let mut fir: FirFilter<Complex32> = FirFilter::new(&taps).unwrap();
+        let expected = process(&mut fir, &input, &[200]);
+        let mut conv: FftConvolver<Complex32> = FftConvolver::new(&taps, 32, Method::OverlapSave).unwrap();
+        let mut partitioned: PartitionedConvolver<Complex32> = PartitionedConvolver::new(&taps, 8).unwrap();
+        let output = process(&mut conv, &input, &[13]);
+        let output2 = process(&mut partitioned, &input, &[13]);
+        for i in 0..200 {
+            assert!((output[i] - expected[i]).norm() < 1e-4);
+            assert!((output2[i] - expected[i]).norm() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_partitioned_convolver() {
+        let taps = taps(1_000);
+        let input = signal(2_000);
+        let mut fir: FirFilter<f64> = FirFilter::new(&taps.iter().map(|t| *t as f64).collect::<Vec<f64>>()).unwrap();
+        let input64: Vec<f64> = input.iter().map(|x| *x as f64).collect();
+        let expected = process(&mut fir, &input64, &[2_000]);
+        let mut conv: PartitionedConvolver<f64> = PartitionedConvolver::new(
+            &taps.iter().map(|t| *t as f64).collect::<Vec<f64>>(), 64).unwrap();
+        assert_eq!(conv.num_partitions(), 16);
+        let output = process(&mut conv, &input64, &[64, 100, 3, 1]);
+        for (y, e) in output.iter().zip(&expected) {
+            assert_approx_eq!(y, e, 1e-9);
+        }
+        conv.reset();
+        let output = process(&mut conv, &input64, &[256]);
+        assert_approx_eq!(output[1_999], expected[1_999], 1e-9);
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert!(FftConvolver::<f32>::new(&[], 16, Method::OverlapAdd).is_err());
+        assert!(FftConvolver::<f32>::new(&[1.0], 0, Method::OverlapAdd).is_err());
+        assert!(PartitionedConvolver::<f32>::new(&[1.0], 0).is_err());
+    }
+}
@@ -0,0 +1,289 @@
+//! FIR filter design with the window method
+//! 
+//! Ideal (sinc) impulse response is truncated to the given number of taps and multiplied
+//! by the window from the `window` module. Taps are normalized to have unit gain
+//! in the center of the pass band.
+//! 
+//! Example
+//! 
+//! ```
+//! use dsp::window;
+//! use dsp::filter::fir::FirFilter;
+//! use dsp::filter::design::fir;
+//! 
+//! // 1kHz lowpass with Hamming window
+//! let taps = fir::lowpass(63, 1_000.0, 48_000, window::hamming).unwrap();
+//! let filter: FirFilter<f32> = FirFilter::new(&taps).unwrap();
+//! 
+//! // Bandpass with Kaiser window with 60dB attenuation and 500Hz transition band
+//! let (num_taps, beta) = fir::kaiser_order(60.0, 500.0, 48_000);
+//! let taps = fir::bandpass(num_taps, 5_000.0, 8_000.0, 48_000, |n| window::kaiser(n, beta)).unwrap();
+//! ```
+
+use std::f64::consts::PI;
+use crate::{Error, Result};
+use crate::window::Window;
+
+
+/// Design lowpass filter
+///   * num_taps - Filter length
+///   * cutoff - Cutoff frequency in Hz
+///   * sample_rate - Number of samples/s
+///   * window - Window function, e.g. `window::hamming`
+pub fn lowpass<W>(num_taps: usize, cutoff: f32, sample_rate: usize, window: W) -> Result<Vec<f32>> 
+where 
+    W: Fn(usize) -> Window
+{
+    let fc = normalize(cutoff, sample_rate)?;
+    firwin(num_taps, &[(0.0, fc)], 0.0, window)
+}
+
+/// Design highpass filter. Number of taps has to be odd.
+///   * num_taps - Filter length
+///   * cutoff - Cutoff frequency in Hz
+///   * sample_rate - Number of samples/s
+///   * window - Window function, e.g. `window::hamming`
+pub fn highpass<W>(num_taps: usize, cutoff: f32, sample_rate: usize, window: W) -> Result<Vec<f32>> 
+where 
+    W: Fn(usize) -> Window
+{
+    let fc = normalize(cutoff, sample_rate)?;
+    check_odd(num_taps)?;
+    firwin(num_taps, &[(fc, 1.0)], 1.0, window)
+}
+
+/// Design bandpass filter
+///   * num_taps - Filter length
+///   * low - Lower cutoff frequency in Hz
+///   * high - Upper cutoff frequency in Hz
+///   * sample_rate - Number of samples/s
+///   * window - Window function, e.g. `window::hamming`
+pub fn bandpass<W>(num_taps: usize, low: f32, high: f32, sample_rate: usize, window: W) -> Result<Vec<f32>> 
+where 
+    W: Fn(usize) -> Window
+{
+    let (f1, f2) = normalize_band(low, high, sample_rate)?;
+    firwin(num_taps, &[(f1, f2)], (f1 + f2) / 2.0, window)
+}
+
+/// Design bandstop filter. Number of taps has to be odd.
+///   * num_taps - Filter length
+///   * low - Lower cutoff frequency in Hz
+///   * high - Upper cutoff frequency in Hz
+///   * sample_rate - Number of samples/s
+///   * window - Window function, e.g. `window::hamming`
+pub fn bandstop<W>(num_taps: usize, low: f32, high: f32, sample_rate: usize, window: W) -> Result<Vec<f32>> 
+where 
+    W: Fn(usize) -> Window
+{
+    let (f1, f2) = normalize_band(low, high, sample_rate)?;
+    check_odd(num_taps)?;
+    firwin(num_taps, &[(0.0, f1), (f2, 1.0)], 0.0, window)
+}
+
+/// Design Hilbert transformer (90° phase shift). Number of taps has to be odd.
+/// Gain is 1 except near 0 and Nyquist frequency, where the width of the transition
+/// depends on the filter length.
+///   * num_taps - Filter length
+///   * window - Window function, e.g. `window::hamming`
+pub fn hilbert<W>(num_taps: usize, window: W) -> Result<Vec<f32>> 
+where 
+    W: Fn(usize) -> Window
+{
+    check_odd(num_taps)?;
+    let win = window(num_taps);
+    if win.len() != num_taps {
+        return Err(Error::Config("Window size doesn't match number of taps".to_string()));
+    }
+    let center = (num_taps / 2) as isize;
+    let taps = (0..num_taps)
+        .map(|i| {
+            // h[k] = 2 / (πk) for odd k, 0 otherwise
+            let k = i as isize - center;
+            let h = if k % 2 == 0 { 0.0 } else { 2.0 / (PI * k as f64) };
+            (h * win.as_slice()[i] as f64) as f32
+        })
+        .collect();
+    Ok(taps)
+}
+
+/// Calculate beta parameter of the Kaiser window for the given stopband attenuation in dB
+pub fn kaiser_beta(attenuation: f32) -> f32 {
+    if attenuation > 50.0 {
+        0.1102 * (attenuation - 8.7)
+    } else if attenuation > 21.0 {
+        0.5842 * (attenuation - 21.0).powf(0.4) + 0.07886 * (attenuation - 21.0)
+    } else {
+        0.0
+    }
+}
+
+/// Estimate Kaiser window parameters for the filter specification.
+///   * attenuation - Stopband attenuation in dB (e.g. 60 for ripple δ = 0.001)
+///   * transition_width - Width of the transition band in Hz
+///   * sample_rate - Number of samples/s
+/// 
+/// Returns number of taps and beta parameter of the Kaiser window.
+/// Number of taps is always odd, so it can be used with any filter type.
+pub fn kaiser_order(attenuation: f32, transition_width: f32, sample_rate: usize) -> (usize, f32) {
+    let width = 2.0 * transition_width as f64 / sample_rate as f64;
+    let n = ((attenuation as f64 - 7.95) / (2.285 * PI * width)).ceil().max(0.0) as usize + 1;
+    let num_taps = if n % 2 == 0 { n + 1 } else { n };
+    (num_taps, kaiser_beta(attenuation))
+}
+
+
+/// Windowed sinc filter
+///   * bands - Pass bands as frequencies normalized to Nyquist frequency
+///   * scale_freq - Normalized frequency where the gain is set to 1
+fn firwin<W>(num_taps: usize, bands: &[(f64, f64)], scale_freq: f64, window: W) -> Result<Vec<f32>> 
+where 
+    W: Fn(usize) -> Window
+{
+    if num_taps < 2 {
+        return Err(Error::Config("Number of taps must be at least 2".to_string()));
+    }
+    let win = window(num_taps);
+    if win.len() != num_taps {
+        return Err(Error::Config("Window size doesn't match number of taps".to_string()));
+    }
+    let alpha = (num_taps - 1) as f64 / 2.0;
+    let mut taps: Vec<f64> = (0..num_taps)
+        .map(|i| {
+            let m = i as f64 - alpha;
+            let h: f64 = bands.iter()
+                .map(|(left, right)| right * sinc(right * m) - left * sinc(left * m))
+                .sum();
+            h * win.as_slice()[i] as f64
+        })
+        .collect();
+
+    // Normalize gain at the scale frequency
+    let gain: f64 = taps.iter().enumerate()
+        .map(|(i, h)| h * (PI * (i as f64 - alpha) * scale_freq).cos())
+        .sum();
+    taps.iter_mut().for_each(|h| *h /= gain);
+    Ok(taps.iter().map(|h| *h as f32).collect())
+}
+
+fn sinc(x: f64) -> f64 {
+    if x == 0.0 { 1.0 } else { (PI * x).sin() / (PI * x) }
+}
+
+/// Convert frequency in Hz into frequency relative to Nyquist frequency
+fn normalize(freq: f32, sample_rate: usize) -> Result<f64> {
+    let nyquist = sample_rate as f64 / 2.0;
+    if freq <= 0.0 || freq as f64 >= nyquist {
+        Err(Error::Config(format!("Cutoff frequency {} should be between 0 and {}", freq, nyquist)))
+    } else {
+        Ok(freq as f64 / nyquist)
+    }
+}
+
+fn normalize_band(low: f32, high: f32, sample_rate: usize) -> Result<(f64, f64)> {
+    if low >= high {
+        return Err(Error::Config(format!("Lower cutoff frequency {} should be less then {}", low, high)));
+    }
+    Ok((normalize(low, sample_rate)?, normalize(high, sample_rate)?))
+}
+
+/// Filter with even number of taps has zero at the Nyquist frequency
+fn check_odd(num_taps: usize) -> Result<()> {
+    if num_taps % 2 == 0 {
+        Err(Error::Config("Filter passing Nyquist frequency requires odd number of taps".to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use crate::window;
+    use super::*;
+
+    const SAMPLE_RATE: usize = 8_000;
+
+    /// Filter gain at the given frequency
+    fn gain(taps: &[f32], freq: f32) -> f32 {
+        let w = 2.0 * PI * freq as f64 / SAMPLE_RATE as f64;
+        let (re, im) = taps.iter().enumerate()
+            .fold((0.0, 0.0), |(re, im), (i, h)| {
+                (re + *h as f64 * (w * i as f64).cos(), im - *h as f64 * (w * i as f64).sin())
+            });
+        (re * re + im * im).sqrt() as f32
+    }
+
+    #[test]
+    fn test_lowpass() {
+        let taps = lowpass(101, 1_000.0, SAMPLE_RATE, window::hamming).unwrap();
+        assert_eq!(taps.len(), 101);
+        assert_approx_eq!(taps[0], taps[100], 1e-7f32);
+        assert_approx_eq!(taps.iter().sum::<f32>(), 1.0, 1e-5f32);
+        assert_approx_eq!(gain(&taps, 500.0), 1.0, 0.01);
+        assert!(gain(&taps, 1_500.0) < 0.01);
+    }
+
+    #[test]
+    fn test_highpass() {
+        let taps = highpass(101, 1_000.0, SAMPLE_RATE, window::blackman).unwrap();
+        assert_approx_eq!(gain(&taps, 4_000.0), 1.0, 1e-4);
+        assert_approx_eq!(gain(&taps, 2_000.0), 1.0, 0.01);
+        assert!(gain(&taps, 200.0) < 0.01);
+        assert!(highpass(100, 1_000.0, SAMPLE_RATE, window::blackman).is_err());
+    }
+
+    #[test]
+    fn test_bandpass_bandstop() {
+        let taps = bandpass(201, 1_000.0, 2_000.0, SAMPLE_RATE, window::hamming).unwrap();
+        assert_approx_eq!(gain(&taps, 1_500.0), 1.0, 0.01);
+        assert!(gain(&taps, 500.0) < 0.01);
+        assert!(gain(&taps, 3_000.0) < 0.01);
+
+        let taps = bandstop(201, 1_000.0, 2_000.0, SAMPLE_RATE, window::hamming).unwrap();
+        assert_approx_eq!(gain(&taps, 0.0), 1.0, 1e-4);
+        assert!(gain(&taps, 1_500.0) < 0.01);
+        assert_approx_eq!(gain(&taps, 3_000.0), 1.0, 0.01);
+    }
+
+    #[test]
+    fn test_kaiser() {
+        let (num_taps, beta) = kaiser_order(60.0, 200.0, SAMPLE_RATE);
+        assert_eq!(num_taps % 2, 1);
+        assert_approx_eq!(beta, 5.65326, 1e-4);
+        let taps = lowpass(num_taps, 1_000.0, SAMPLE_RATE, |n| window::kaiser(n, beta)).unwrap();
+        // Stop band starts at cutoff + half of the transition width
+        for f in [1_100.0, 1_500.0, 2_500.0, 3_900.0] {
+            assert!(20.0 * gain(&taps, f).log10() < -59.0, "Gain too high at {}", f);
+        }
+        for f in [0.0, 500.0, 900.0] {
+            assert_approx_eq!(gain(&taps, f), 1.0, 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_hilbert() {
+        let taps = hilbert(65, window::blackman).unwrap();
+        // Antisymmetric with zeros at even offsets from the center
+        assert_eq!(taps[32], 0.0);
+        assert_eq!(taps[30], 0.0);
+        assert_eq!(taps[31], -taps[33]);
+        assert!(taps[33] > 0.6);
+        for f in [500.0, 2_000.0, 3_500.0] {
+            assert_approx_eq!(gain(&taps, f), 1.0, 0.01);
+        }
+        assert!(hilbert(64, window::blackman).is_err());
+    }
+
+    #[test]
+    fn test_invalid_cutoff() {
+        assert!(lowpass(11, 4_000.0, SAMPLE_RATE, window::hamming).is_err());
+        assert!(bandpass(11, 2_000.0, 1_000.0, SAMPLE_RATE, window::hamming).is_err());
+        assert!(lowpass(0, 1_000.0, SAMPLE_RATE, window::hamming).is_err());
+        assert!(lowpass(1, 1_000.0, SAMPLE_RATE, window::hamming).is_err());
+    }
+}
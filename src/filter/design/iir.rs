@@ -0,0 +1,652 @@
+//! IIR filter design from the analog prototypes
+//!
+//! Design steps:
+//!   1. Normalized analog lowpass prototype (Butterworth, Chebyshev, elliptic, Bessel)
+//!   2. Frequency transformation into lowpass, highpass, bandpass or bandstop at prewarped frequencies
+//!   3. Bilinear transform into the digital filter
+//!   4. Grouping poles and zeros into the second order sections
+//!
+//! All calculations are done on the zero-pole-gain representation in f64.
+//!
+//! Example
+//!
+//! ```
+//! use dsp::filter::design::iir::{self, Band, Prototype};
+//!
+//! // 4th order Butterworth lowpass with cutoff at 1kHz
+//! let sections = iir::butterworth(4, Band::Lowpass(1_000.0), 8_000).unwrap();
+//! assert_eq!(sections.len(), 2);
+//!
+//! // Elliptic bandpass with 0.5dB ripple and 60dB attenuation
+//! let sections = iir::design(Prototype::Elliptic { ripple: 0.5, attenuation: 60.0 },
+//!                            3, Band::Bandpass(1_000.0, 2_000.0), 8_000).unwrap();
+//! assert_eq!(sections.len(), 3);
+//! ```
+
+use std::f64::consts::PI;
+use num_complex::Complex64;
+use num_traits::cast;
+use crate::{Error, Result};
+use crate::filter::biquad::BiquadFilter;
+use crate::sample::Sample;
+use super::poly;
+
+
+/// Frequency band of the filter. Frequencies are in Hz.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Band {
+    Lowpass(f32),
+    Highpass(f32),
+    /// Lower and upper cutoff frequency
+    Bandpass(f32, f32),
+    /// Lower and upper cutoff frequency
+    Bandstop(f32, f32),
+}
+
+/// Analog lowpass prototype
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Prototype {
+    /// Maximally flat pass band. Gain at cutoff is -3dB
+    Butterworth,
+    /// Ripple in pass band (dB). Gain at cutoff is -ripple dB
+    Chebyshev1 { ripple: f32 },
+    /// Ripple in stop band. Gain at cutoff is -attenuation dB
+    Chebyshev2 { attenuation: f32 },
+    /// Ripple in both bands. Gain at cutoff is -ripple dB
+    Elliptic { ripple: f32, attenuation: f32 },
+    /// Maximally flat group delay. Gain at cutoff is -3dB
+    Bessel,
+}
+
+/// Filter described by its zeros, poles and gain:
+///
+/// H(s) = gain * prod(s - zeros) / prod(s - poles)
+#[derive(Clone, Debug)]
+pub struct Zpk {
+    pub zeros: Vec<Complex64>,
+    pub poles: Vec<Complex64>,
+    pub gain: f64,
+}
+
+
+/// Design digital IIR filter as a cascade of biquad sections.
+///   * prototype - Analog prototype
+///   * order - Order of the prototype. Bandpass and bandstop filters have double order.
+///   * band - Filter type and cutoff frequencies
+///   * sample_rate - Number of samples/s
+pub fn design(prototype: Prototype, order: usize, band: Band, sample_rate: usize) -> Result<Vec<BiquadFilter>> {
+    digital(prototype, order, band, sample_rate)?.to_biquads()
+}
+
+/// Design digital IIR filter in the zero-pole-gain form.
+/// Parameters are the same as in the function `design`.
+pub fn digital(prototype: Prototype, order: usize, band: Band, sample_rate: usize) -> Result<Zpk> {
+    let fs = sample_rate as f64;
+    let warp = |f: f32| -> Result<f64> {
+        if f <= 0.0 || f as f64 >= fs / 2.0 {
+            return Err(Error::Config(format!("Cutoff frequency {} should be between 0 and {}", f, fs / 2.0)));
+        }
+        Ok(prewarp(f as f64, fs))
+    };
+    let analog = prototype.analog(order)?;
+    let zpk = match band {
+        Band::Lowpass(f) => analog.lowpass(warp(f)?),
+        Band::Highpass(f) => analog.highpass(warp(f)?),
+        Band::Bandpass(low, high) | Band::Bandstop(low, high) => {
+            if low >= high {
+                return Err(Error::Config(format!("Lower cutoff frequency {} should be less then {}", low, high)));
+            }
+            let (w1, w2) = (warp(low)?, warp(high)?);
+            let (center, width) = ((w1 * w2).sqrt(), w2 - w1);
+            if let Band::Bandpass(_, _) = band {
+                analog.bandpass(center, width)
+            } else {
+                analog.bandstop(center, width)
+            }
+        },
+    };
+    Ok(zpk.bilinear(fs))
+}
+
+/// Butterworth filter as a cascade of biquads
+pub fn butterworth(order: usize, band: Band, sample_rate: usize) -> Result<Vec<BiquadFilter>> {
+    design(Prototype::Butterworth, order, band, sample_rate)
+}
+
+/// Chebyshev type I filter with the given pass band ripple in dB
+pub fn chebyshev1(order: usize, ripple: f32, band: Band, sample_rate: usize) -> Result<Vec<BiquadFilter>> {
+    design(Prototype::Chebyshev1 { ripple }, order, band, sample_rate)
+}
+
+/// Chebyshev type II filter with the given stop band attenuation in dB
+pub fn chebyshev2(order: usize, attenuation: f32, band: Band, sample_rate: usize) -> Result<Vec<BiquadFilter>> {
+    design(Prototype::Chebyshev2 { attenuation }, order, band, sample_rate)
+}
+
+/// Elliptic (Cauer) filter with the given pass band ripple and stop band attenuation in dB
+pub fn elliptic(order: usize, ripple: f32, attenuation: f32, band: Band, sample_rate: usize) -> Result<Vec<BiquadFilter>> {
+    design(Prototype::Elliptic { ripple, attenuation }, order, band, sample_rate)
+}
+
+/// Bessel filter
+pub fn bessel(order: usize, band: Band, sample_rate: usize) -> Result<Vec<BiquadFilter>> {
+    design(Prototype::Bessel, order, band, sample_rate)
+}
+
+/// Analog frequency (rad/s) which is mapped by the bilinear transform into the given frequency in Hz
+pub fn prewarp(freq: f64, sample_rate: f64) -> f64 {
+    2.0 * sample_rate * (PI * freq / sample_rate).tan()
+}
+
+
+impl Prototype {
+    /// Analog lowpass prototype with cutoff at 1 rad/s
+    pub fn analog(&self, order: usize) -> Result<Zpk> {
+        if order == 0 {
+            return Err(Error::Config("Filter order must be greater then 0".to_string()));
+        }
+        let check_db = |v: f32, name: &str| {
+            if v > 0.0 { Ok(v as f64) } else { Err(Error::Config(format!("{} must be positive", name))) }
+        };
+        match *self {
+            Prototype::Butterworth => Ok(butterworth_prototype(order)),
+            Prototype::Chebyshev1 { ripple } => Ok(chebyshev1_prototype(order, check_db(ripple, "Ripple")?)),
+            Prototype::Chebyshev2 { attenuation } => Ok(chebyshev2_prototype(order, check_db(attenuation, "Attenuation")?)),
+            Prototype::Elliptic { ripple, attenuation } => {
+                let (rp, rs) = (check_db(ripple, "Ripple")?, check_db(attenuation, "Attenuation")?);
+                if rs <= rp {
+                    return Err(Error::Config("Attenuation must be greater then ripple".to_string()));
+                }
+                Ok(elliptic_prototype(order, rp, rs))
+            },
+            Prototype::Bessel => Ok(bessel_prototype(order)),
+        }
+    }
+}
+
+
+impl Zpk {
+    /// Evaluate analog transfer function at the point s (or digital at the point z)
+    pub fn eval(&self, s: Complex64) -> Complex64 {
+        let num = self.zeros.iter().fold(Complex64::new(self.gain, 0.0), |acc, z| acc * (s - z));
+        self.poles.iter().fold(num, |acc, p| acc / (s - p))
+    }
+
+    /// Analog lowpass with cutoff at `wo` rad/s from the lowpass prototype
+    pub fn lowpass(&self, wo: f64) -> Zpk {
+        let degree = self.degree();
+        Zpk {
+            zeros: self.zeros.iter().map(|z| z * wo).collect(),
+            poles: self.poles.iter().map(|p| p * wo).collect(),
+            gain: self.gain * wo.powi(degree as i32),
+        }
+    }
+
+    /// Analog highpass with cutoff at `wo` rad/s from the lowpass prototype
+    pub fn highpass(&self, wo: f64) -> Zpk {
+        let mut zeros: Vec<Complex64> = self.zeros.iter().map(|z| wo / z).collect();
+        zeros.extend(vec![Complex64::new(0.0, 0.0); self.degree()]);
+        Zpk {
+            zeros,
+            poles: self.poles.iter().map(|p| wo / p).collect(),
+            gain: self.gain * (product(&self.zeros) / product(&self.poles)).re,
+        }
+    }
+
+    /// Analog bandpass with center frequency `wo` and bandwidth `bw` (rad/s) from the lowpass prototype
+    pub fn bandpass(&self, wo: f64, bw: f64) -> Zpk {
+        let transform = |x: &Complex64| {
+            let x = x * bw / 2.0;
+            let d = (x * x - wo * wo).sqrt();
+            [x + d, x - d]
+        };
+        let mut zeros: Vec<Complex64> = self.zeros.iter().flat_map(transform).collect();
+        zeros.extend(vec![Complex64::new(0.0, 0.0); self.degree()]);
+        Zpk {
+            zeros,
+            poles: self.poles.iter().flat_map(transform).collect(),
+            gain: self.gain * bw.powi(self.degree() as i32),
+        }
+    }
+
+    /// Analog bandstop with center frequency `wo` and bandwidth `bw` (rad/s) from the lowpass prototype
+    pub fn bandstop(&self, wo: f64, bw: f64) -> Zpk {
+        let transform = |x: &Complex64| {
+            let x = bw / 2.0 / x;
+            let d = (x * x - wo * wo).sqrt();
+            [x + d, x - d]
+        };
+        let mut zeros: Vec<Complex64> = self.zeros.iter().flat_map(transform).collect();
+        for _ in 0..self.degree() {
+            zeros.push(Complex64::new(0.0, wo));
+            zeros.push(Complex64::new(0.0, -wo));
+        }
+        Zpk {
+            zeros,
+            poles: self.poles.iter().flat_map(transform).collect(),
+            gain: self.gain * (product(&self.zeros) / product(&self.poles)).re,
+        }
+    }
+
+    /// Digital filter from the analog one with the bilinear transform
+    pub fn bilinear(&self, sample_rate: f64) -> Zpk {
+        let fs2 = 2.0 * sample_rate;
+        let transform = |x: &Complex64| (fs2 + x) / (fs2 - x);
+        let mut zeros: Vec<Complex64> = self.zeros.iter().map(transform).collect();
+        zeros.extend(vec![Complex64::new(-1.0, 0.0); self.degree()]);
+        let num = self.zeros.iter().fold(Complex64::new(1.0, 0.0), |acc, z| acc * (fs2 - z));
+        let den = self.poles.iter().fold(Complex64::new(1.0, 0.0), |acc, p| acc * (fs2 - p));
+        Zpk {
+            zeros,
+            poles: self.poles.iter().map(transform).collect(),
+            gain: self.gain * (num / den).re,
+        }
+    }
+
+    /// Coefficients (b, a) of the second order sections of the digital filter.
+    /// Complex poles and zeros are grouped with their conjugates, and each pole pair
+    /// is matched with the closest zeros. Sections are ordered with poles closest to
+    /// the unit circle last. The gain is applied to the first section.
+    pub fn sections(&self) -> Vec<([f64; 3], [f64; 3])> {
+        let mut pole_pairs = conjugate_pairs(&self.poles);
+        let mut zero_pairs = conjugate_pairs(&self.zeros);
+        pole_pairs.sort_by(|a, b| a[0].norm().partial_cmp(&b[0].norm()).unwrap());
+        let num_sections = usize::max(pole_pairs.len(), zero_pairs.len()).max(1);
+
+        // Match zeros starting from the poles closest to the unit circle
+        let mut sections = vec![];
+        for i in (0..num_sections).rev() {
+            let poles = pole_pairs.get(i).cloned().unwrap_or_default();
+            let zeros = if zero_pairs.is_empty() {
+                vec![]
+            } else {
+                let target = poles.first().cloned().unwrap_or_default();
+                let best = (0..zero_pairs.len())
+                    .min_by(|&a, &b| {
+                        let da = (zero_pairs[a][0] - target).norm();
+                        let db = (zero_pairs[b][0] - target).norm();
+                        da.partial_cmp(&db).unwrap()
+                    })
+                    .unwrap();
+                zero_pairs.remove(best)
+            };
+            sections.push((quadratic(&zeros), quadratic(&poles)));
+        }
+        sections.reverse();
+        for k in 0..3 {
+            sections[0].0[k] *= self.gain;
+        }
+        sections
+    }
+
+    /// Digital filter as a cascade of biquads.
+    /// Sample type can be any of the types supported by the filters, e.g. `to_biquads::<Complex32>()`
+    pub fn to_biquads<T: Sample>(&self) -> Result<Vec<BiquadFilter<T>>> {
+        self.sections().iter()
+            .map(|(b, a)| {
+                let b: Vec<T::Real> = b.iter().map(|v| cast(*v).unwrap()).collect();
+                let a: Vec<T::Real> = a.iter().map(|v| cast(*v).unwrap()).collect();
+                BiquadFilter::new(&b, &a)
+            })
+            .collect()
+    }
+
+    /// Difference between number of poles and zeros
+    fn degree(&self) -> usize {
+        self.poles.len().saturating_sub(self.zeros.len())
+    }
+}
+
+
+/// Product of (-x) over all values
+fn product(xs: &[Complex64]) -> Complex64 {
+    xs.iter().fold(Complex64::new(1.0, 0.0), |acc, x| acc * -x)
+}
+
+/// Split values into conjugate pairs and pairs of real values. Last group can have single value.
+fn conjugate_pairs(xs: &[Complex64]) -> Vec<Vec<Complex64>> {
+    let eps = 1e-10;
+    let mut groups: Vec<Vec<Complex64>> = xs.iter()
+        .filter(|x| x.im > eps * x.norm().max(1.0))
+        .map(|x| vec![*x, x.conj()])
+        .collect();
+    let mut reals: Vec<Complex64> = xs.iter()
+        .filter(|x| x.im.abs() <= eps * x.norm().max(1.0))
+        .map(|x| Complex64::new(x.re, 0.0))
+        .collect();
+    reals.sort_by(|a, b| a.re.partial_cmp(&b.re).unwrap());
+    groups.extend(reals.chunks(2).map(|c| c.to_vec()));
+    groups
+}
+
+/// Coefficients of the polynomial in z^-1 with the given roots (at most 2)
+fn quadratic(roots: &[Complex64]) -> [f64; 3] {
+    let coeffs = poly::from_roots(roots);
+    let mut output = [0.0; 3];
+    for i in 0..coeffs.len() {
+        output[i] = coeffs[i].re;
+    }
+    output
+}
+
+
+fn butterworth_prototype(order: usize) -> Zpk {
+    let n = order as f64;
+    let poles = (0..order)
+        .map(|k| Complex64::from_polar(1.0, PI * (2.0 * k as f64 + n + 1.0) / (2.0 * n)))
+        .collect();
+    Zpk { zeros: vec![], poles, gain: 1.0 }
+}
+
+fn chebyshev1_prototype(order: usize, ripple: f64) -> Zpk {
+    let n = order as f64;
+    let eps = (10f64.powf(0.1 * ripple) - 1.0).sqrt();
+    let mu = (1.0 / eps).asinh() / n;
+    let poles: Vec<Complex64> = (0..order)
+        .map(|k| {
+            let theta = PI * (2.0 * k as f64 - n + 1.0) / (2.0 * n);
+            -Complex64::new(mu, theta).sinh()
+        })
+        .collect();
+    let mut gain = product(&poles).re;
+    if order % 2 == 0 {
+        gain /= (1.0 + eps * eps).sqrt();
+    }
+    Zpk { zeros: vec![], poles, gain }
+}
+
+fn chebyshev2_prototype(order: usize, attenuation: f64) -> Zpk {
+    let n = order as f64;
+    let de = 1.0 / (10f64.powf(0.1 * attenuation) - 1.0).sqrt();
+    let mu = (1.0 / de).asinh() / n;
+    let zeros: Vec<Complex64> = (0..order)
+        .map(|k| 2.0 * k as f64 - n + 1.0)
+        .filter(|m| *m != 0.0)
+        .map(|m| -(Complex64::i() / (m * PI / (2.0 * n)).sin()).conj())
+        .collect();
+    let poles: Vec<Complex64> = (0..order)
+        .map(|k| {
+            let p = -Complex64::from_polar(1.0, PI * (2.0 * k as f64 - n + 1.0) / (2.0 * n));
+            1.0 / Complex64::new(mu.sinh() * p.re, mu.cosh() * p.im)
+        })
+        .collect();
+    let gain = (product(&poles) / product(&zeros)).re;
+    Zpk { zeros, poles, gain }
+}
+
+fn elliptic_prototype(order: usize, ripple: f64, attenuation: f64) -> Zpk {
+    let eps = (10f64.powf(0.1 * ripple) - 1.0).sqrt();
+    if order == 1 {
+        let p = -1.0 / eps;
+        return Zpk { zeros: vec![], poles: vec![Complex64::new(p, 0.0)], gain: -p };
+    }
+    let n = order as f64;
+    let ck1 = eps / (10f64.powf(0.1 * attenuation) - 1.0).sqrt();
+    let ck1p = (1.0 - ck1 * ck1).sqrt();
+    let (k1, k1p) = (ellipk(ck1 * ck1), ellipk(ck1p * ck1p));
+
+    // Selectivity parameter m for which K(m)/K(1-m) = n * K(ck1^2)/K(ck1p^2)
+    let m = modulus_from_ratio(n * k1 / k1p);
+    let capk = ellipk(m);
+
+    let js: Vec<f64> = ((order % 2 == 0) as usize..order).step_by(2).map(|j| j as f64).collect();
+    let sncndn: Vec<(f64, f64, f64)> = js.iter().map(|j| ellipj(j * capk / n, m)).collect();
+    let mut zeros: Vec<Complex64> = sncndn.iter()
+        .filter(|(s, _, _)| s.abs() > 1e-12)
+        .map(|(s, _, _)| Complex64::new(0.0, 1.0 / (m.sqrt() * s)))
+        .collect();
+    let conj: Vec<Complex64> = zeros.iter().map(|z| z.conj()).collect();
+    zeros.extend(conj);
+
+    // v0 is the solution of sn(v, ck1p^2)/cn(v, ck1p^2) = 1/eps
+    let r = ellipf((1.0 / eps).atan(), ck1p * ck1p);
+    let v0 = capk * r / (n * k1);
+    let (sv, cv, dv) = ellipj(v0, 1.0 - m);
+    let mut poles: Vec<Complex64> = sncndn.iter()
+        .map(|(s, c, d)| -Complex64::new(c * d * sv * cv, s * dv) / (1.0 - (d * sv).powi(2)))
+        .collect();
+    let norm = poles.iter().map(|p| p.norm_sqr()).sum::<f64>().sqrt();
+    let conj: Vec<Complex64> = poles.iter()
+        .filter(|p| p.im.abs() > 1e-12 * norm)
+        .map(|p| p.conj())
+        .collect();
+    poles.extend(conj);
+
+    let mut gain = (product(&poles) / product(&zeros)).re;
+    if order % 2 == 0 {
+        gain /= (1.0 + eps * eps).sqrt();
+    }
+    Zpk { zeros, poles, gain }
+}
+
+fn bessel_prototype(order: usize) -> Zpk {
+    // Reverse Bessel polynomial: a_k = (2n-k)! / (2^(n-k) k! (n-k)!)
+    let n = order;
+    let mut coeffs = vec![0.0; n + 1];
+    let mut a = 1.0;
+    for k in (0..=n).rev() {
+        coeffs[n - k] = a;
+        // a_(k-1) = a_k * 2k(2n-k+1) / ((n-k+1) * 2)  [ratio of neighbour coefficients]
+        if k > 0 {
+            a *= (2 * n - k + 1) as f64 * k as f64 / (2 * (n - k + 1)) as f64;
+        }
+    }
+    let poles = poly::roots(&coeffs);
+    let zpk = Zpk { gain: product(&poles).re, zeros: vec![], poles };
+
+    // Scale, so the gain at 1 rad/s is -3dB
+    let half_power = |w: f64| zpk.eval(Complex64::new(0.0, w)).norm_sqr() - 0.5;
+    let (mut low, mut high) = (0.0, 1.0);
+    while half_power(high) > 0.0 {
+        high *= 2.0;
+    }
+    for _ in 0..100 {
+        let mid = (low + high) / 2.0;
+        if half_power(mid) > 0.0 { low = mid; } else { high = mid; }
+    }
+    zpk.lowpass(1.0 / low)
+}
+
+
+/// Complete elliptic integral of the first kind K(m)
+fn ellipk(m: f64) -> f64 {
+    let (mut a, mut b) = (1.0, (1.0 - m).sqrt());
+    while (a - b).abs() > 1e-15 * a {
+        (a, b) = ((a + b) / 2.0, (a * b).sqrt());
+    }
+    PI / (2.0 * a)
+}
+
+/// Incomplete elliptic integral of the first kind F(phi|m), with Carlson's R_F
+fn ellipf(phi: f64, m: f64) -> f64 {
+    let s = phi.sin();
+    let c = phi.cos();
+    let (mut x, mut y, mut z) = (c * c, 1.0 - m * s * s, 1.0);
+    loop {
+        let mu = (x + y + z) / 3.0;
+        let (dx, dy, dz) = (1.0 - x / mu, 1.0 - y / mu, 1.0 - z / mu);
+        if dx.abs().max(dy.abs()).max(dz.abs()) < 1e-6 {
+            let e2 = dx * dy - dz * dz;
+            let e3 = dx * dy * dz;
+            return s * (1.0 + (e2 / 24.0 - 0.1 - 3.0 * e3 / 44.0) * e2 + e3 / 14.0) / mu.sqrt();
+        }
+        let (sx, sy, sz) = (x.sqrt(), y.sqrt(), z.sqrt());
+        let lambda = sx * (sy + sz) + sy * sz;
+        x = (x + lambda) / 4.0;
+        y = (y + lambda) / 4.0;
+        z = (z + lambda) / 4.0;
+    }
+}
+
+/// Jacobi elliptic functions (sn, cn, dn) with the arithmetic-geometric mean method
+fn ellipj(u: f64, m: f64) -> (f64, f64, f64) {
+    if m < 1e-9 {
+        return (u.sin(), u.cos(), 1.0);
+    }
+    let mut a = vec![1.0];
+    let mut c = vec![m.sqrt()];
+    let mut b = (1.0 - m).sqrt();
+    while (c.last().unwrap() / a.last().unwrap()).abs() > 1e-15 && a.len() < 10 {
+        let ai = *a.last().unwrap();
+        c.push((ai - b) / 2.0);
+        a.push((ai + b) / 2.0);
+        b = (ai * b).sqrt();
+    }
+    let i = a.len() - 1;
+    let mut phi = 2f64.powi(i as i32) * a[i] * u;
+    let mut prev = phi;
+    for k in (1..=i).rev() {
+        prev = phi;
+        phi = ((c[k] * phi.sin() / a[k]).asin() + phi) / 2.0;
+    }
+    (phi.sin(), phi.cos(), phi.cos() / (prev - phi).cos())
+}
+
+/// Parameter m for which K(m)/K(1-m) is equal to the given ratio, from the Jacobi theta functions
+fn modulus_from_ratio(ratio: f64) -> f64 {
+    let q = (-PI / ratio).exp();
+    let theta2: f64 = 2.0 * q.powf(0.25) * (0..20).map(|n| q.powi(n * (n + 1))).sum::<f64>();
+    let theta3: f64 = 1.0 + 2.0 * (1..20).map(|n| q.powi(n * n)).sum::<f64>();
+    (theta2 / theta3).powi(4)
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use super::*;
+
+    const SAMPLE_RATE: usize = 8_000;
+
+    /// Gain of the biquad cascade at the given frequency
+    fn gain(sections: &[BiquadFilter], freq: f64) -> f64 {
+        let z = Complex64::from_polar(1.0, -2.0 * PI * freq / SAMPLE_RATE as f64);
+        sections.iter()
+            .map(|s| {
+                let (b, a) = s.coefficients();
+                let num = b[0] as f64 + z * b[1] as f64 + z * z * b[2] as f64;
+                let den = a[0] as f64 + z * a[1] as f64 + z * z * a[2] as f64;
+                (num / den).norm()
+            })
+            .product()
+    }
+
+    fn db(sections: &[BiquadFilter], freq: f64) -> f64 {
+        20.0 * gain(sections, freq).log10()
+    }
+
+    #[test]
+    fn test_butterworth_coefficients() {
+        // scipy.signal.butter(2, 0.5)
+        let sections = butterworth(2, Band::Lowpass(2_000.0), SAMPLE_RATE).unwrap();
+        let (b, a) = sections[0].coefficients();
+        assert_approx_eq!(b[0], 0.29289322, 1e-6);
+        assert_approx_eq!(b[1], 0.58578644, 1e-6);
+        assert_approx_eq!(b[2], 0.29289322, 1e-6);
+        assert_approx_eq!(a[0], 1.0, 1e-6);
+        assert_approx_eq!(a[1], 0.0, 1e-6);
+        assert_approx_eq!(a[2], 0.17157288, 1e-6);
+    }
+
+    #[test]
+    fn test_butterworth() {
+        let sections = butterworth(5, Band::Lowpass(1_000.0), SAMPLE_RATE).unwrap();
+        assert_eq!(sections.len(), 3);
+        assert_approx_eq!(gain(&sections, 0.0), 1.0, 1e-5);
+        assert_approx_eq!(db(&sections, 1_000.0), -3.0103, 1e-3);
+        assert!(db(&sections, 2_000.0) < -30.0);
+
+        let sections = butterworth(4, Band::Highpass(1_000.0), SAMPLE_RATE).unwrap();
+        assert_approx_eq!(gain(&sections, 4_000.0), 1.0, 1e-5);
+        assert_approx_eq!(db(&sections, 1_000.0), -3.0103, 1e-3);
+        assert!(db(&sections, 300.0) < -40.0);
+    }
+
+    #[test]
+    fn test_bandpass_bandstop() {
+        let sections = butterworth(3, Band::Bandpass(1_000.0, 2_000.0), SAMPLE_RATE).unwrap();
+        assert_eq!(sections.len(), 3);
+        assert_approx_eq!(db(&sections, 1_000.0), -3.0103, 1e-3);
+        assert_approx_eq!(db(&sections, 2_000.0), -3.0103, 1e-3);
+        assert!(db(&sections, 200.0) < -40.0);
+        assert!(db(&sections, 3_500.0) < -40.0);
+
+        let sections = butterworth(3, Band::Bandstop(1_000.0, 2_000.0), SAMPLE_RATE).unwrap();
+        assert_approx_eq!(gain(&sections, 0.0), 1.0, 1e-5);
+        assert_approx_eq!(gain(&sections, 4_000.0), 1.0, 1e-5);
+        assert_approx_eq!(db(&sections, 1_000.0), -3.0103, 1e-3);
+        let center = prewarp_center(1_000.0, 2_000.0);
+        assert!(db(&sections, center) < -60.0);
+    }
+
+    /// Digital frequency of the analog center frequency
+    fn prewarp_center(low: f64, high: f64) -> f64 {
+        let fs = SAMPLE_RATE as f64;
+        let wo = (prewarp(low, fs) * prewarp(high, fs)).sqrt();
+        (wo / (2.0 * fs)).atan() * fs / PI
+    }
+
+    #[test]
+    fn test_chebyshev1() {
+        for order in [4, 5] {
+            let sections = chebyshev1(order, 1.0, Band::Lowpass(1_000.0), SAMPLE_RATE).unwrap();
+            assert_approx_eq!(db(&sections, 1_000.0), -1.0, 1e-3);
+            for f in (0..1_000).step_by(50) {
+                let g = db(&sections, f as f64);
+                assert!((-1.0 - 1e-4..=1e-4).contains(&g), "Gain {} at {}", g, f);
+            }
+        }
+    }
+
+    #[test]
+    fn test_chebyshev2() {
+        for order in [4, 5] {
+            let sections = chebyshev2(order, 40.0, Band::Lowpass(1_000.0), SAMPLE_RATE).unwrap();
+            assert_approx_eq!(gain(&sections, 0.0), 1.0, 1e-5);
+            assert_approx_eq!(db(&sections, 1_000.0), -40.0, 1e-3);
+            for f in (1_000..4_000).step_by(50) {
+                assert!(db(&sections, f as f64) < -40.0 + 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_elliptic() {
+        for order in [1, 4, 5] {
+            let sections = elliptic(order, 0.5, 60.0, Band::Lowpass(1_000.0), SAMPLE_RATE).unwrap();
+            assert_approx_eq!(db(&sections, 1_000.0), -0.5, 1e-3);
+            for f in (0..1_000).step_by(50) {
+                let g = db(&sections, f as f64);
+                assert!((-0.5 - 1e-4..=1e-4).contains(&g), "Gain {} at {}", g, f);
+            }
+        }
+        // Stop band starts at 1.7kHz for this specification
+        let sections = elliptic(5, 0.5, 60.0, Band::Lowpass(1_000.0), SAMPLE_RATE).unwrap();
+        for f in (1_700..4_000).step_by(50) {
+            assert!(db(&sections, f as f64) < -60.0 + 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_bessel() {
+        let sections = bessel(4, Band::Lowpass(1_000.0), SAMPLE_RATE).unwrap();
+        assert_approx_eq!(gain(&sections, 0.0), 1.0, 1e-5);
+        assert_approx_eq!(db(&sections, 1_000.0), -3.0103, 1e-3);
+        // Analog prototype poles of the 2nd order Bessel filter (delay normalized: -1.5 ± 0.866j)
+        let zpk = Prototype::Bessel.analog(2).unwrap();
+        let ratio = zpk.poles[0].im.abs() / zpk.poles[0].re.abs();
+        assert_approx_eq!(ratio, 3f64.sqrt() / 3.0, 1e-9);
+    }
+
+    #[test]
+    fn test_invalid_spec() {
+        assert!(butterworth(0, Band::Lowpass(1_000.0), SAMPLE_RATE).is_err());
+        assert!(butterworth(2, Band::Lowpass(4_000.0), SAMPLE_RATE).is_err());
+        assert!(butterworth(2, Band::Bandpass(2_000.0, 1_000.0), SAMPLE_RATE).is_err());
+        assert!(chebyshev1(2, 0.0, Band::Lowpass(1_000.0), SAMPLE_RATE).is_err());
+        assert!(elliptic(2, 40.0, 20.0, Band::Lowpass(1_000.0), SAMPLE_RATE).is_err());
+    }
+}
@@ -0,0 +1,8 @@
+//! # Filter design
+//! 
+//! Functions which calculate filter coefficients from the filter specification
+//! 
+pub mod fir;
+pub mod iir;
+pub mod remez;
+pub(crate) mod poly;
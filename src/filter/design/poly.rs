@@ -0,0 +1,132 @@
+//! Polynomial helpers used by the filter design.
+//! Coefficients are stored from the highest power, like the filter coefficients in z^-1.
+
+use num_complex::Complex64;
+
+
+/// Maximum number of Durand-Kerner iterations
+const MAX_ITERATIONS: usize = 1000;
+
+
+/// Find all complex roots of the polynomial with the Durand-Kerner method
+pub(crate) fn roots(coeffs: &[f64]) -> Vec<Complex64> {
+    // Leading zeros don't change the roots
+    let start = coeffs.iter().position(|c| *c != 0.0).unwrap_or(coeffs.len());
+    let coeffs: Vec<f64> = coeffs[start..].iter().map(|c| c / coeffs[start]).collect();
+    if coeffs.len() < 2 {
+        return vec![];
+    }
+    let degree = coeffs.len() - 1;
+    if degree <= 2 {
+        return low_degree_roots(&coeffs);
+    }
+    let eval = |x: Complex64| coeffs.iter().fold(Complex64::new(0.0, 0.0), |acc, c| acc * x + c);
+
+    // Initial guesses on the circle with radius bounding all roots
+    let radius = 1.0 + coeffs[1..].iter().map(|c| c.abs()).fold(0.0, f64::max);
+    let seed = Complex64::new(0.4, 0.9);
+    let mut roots: Vec<Complex64> = (0..degree)
+        .map(|i| seed.powu(i as u32) * radius / seed.norm().powi(i as i32))
+        .collect();
+    for _ in 0..MAX_ITERATIONS {
+        let mut max_delta: f64 = 0.0;
+        for i in 0..degree {
+            let denom = (0..degree)
+                .filter(|&j| j != i)
+                .fold(Complex64::new(1.0, 0.0), |acc, j| acc * (roots[i] - roots[j]));
+            let delta = eval(roots[i]) / denom;
+            if delta.is_finite() {
+                roots[i] -= delta;
+                max_delta = max_delta.max(delta.norm() / roots[i].norm().max(1.0));
+            }
+        }
+        if max_delta < 1e-14 {
+            break;
+        }
+    }
+    // Remove numerical noise from real roots
+    roots.iter()
+        .map(|r| if r.im.abs() < 1e-10 * r.norm().max(1.0) { Complex64::new(r.re, 0.0) } else { *r })
+        .collect()
+}
+
+/// Roots of the monic linear or quadratic polynomial
+fn low_degree_roots(coeffs: &[f64]) -> Vec<Complex64> {
+    if coeffs.len() == 2 {
+        return vec![Complex64::new(-coeffs[1], 0.0)];
+    }
+    let (b, c) = (coeffs[1], coeffs[2]);
+    let delta = b * b - 4.0 * c;
+    if delta >= 0.0 {
+        // Avoid cancellation in the root with the smaller magnitude
+        let q = -0.5 * (b + b.signum() * delta.sqrt());
+        if q == 0.0 {
+            return vec![Complex64::new(0.0, 0.0); 2];
+        }
+        vec![Complex64::new(q, 0.0), Complex64::new(c / q, 0.0)]
+    } else {
+        let im = (-delta).sqrt() / 2.0;
+        vec![Complex64::new(-b / 2.0, im), Complex64::new(-b / 2.0, -im)]
+    }
+}
+
+/// Polynomial with the given roots
+pub(crate) fn from_roots(roots: &[Complex64]) -> Vec<Complex64> {
+    let mut coeffs = vec![Complex64::new(1.0, 0.0)];
+    for r in roots {
+        coeffs.push(Complex64::new(0.0, 0.0));
+        for i in (1..coeffs.len()).rev() {
+            let prev = coeffs[i - 1];
+            coeffs[i] -= r * prev;
+        }
+    }
+    coeffs
+}
+
+/// Product of two polynomials
+pub(crate) fn multiply(xs: &[f64], ys: &[f64]) -> Vec<f64> {
+    let mut output = vec![0.0; xs.len() + ys.len() - 1];
+    for (i, x) in xs.iter().enumerate() {
+        for (j, y) in ys.iter().enumerate() {
+            output[i + j] += x * y;
+        }
+    }
+    output
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use super::*;
+
+    #[test]
+    fn test_roots() {
+        // (x - 1)(x + 2)(x^2 + 1)
+        let mut rs = roots(&[1.0, 1.0, -1.0, 1.0, -2.0]);
+        rs.sort_by(|a, b| (a.re.round(), a.im).partial_cmp(&(b.re.round(), b.im)).unwrap());
+        let expected = [Complex64::new(-2.0, 0.0), Complex64::new(0.0, -1.0), Complex64::new(0.0, 1.0), Complex64::new(1.0, 0.0)];
+        for (r, e) in rs.iter().zip(expected.iter()) {
+            assert_approx_eq!(r.re, e.re, 1e-9);
+            assert_approx_eq!(r.im, e.im, 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_quadratic_roots() {
+        // (x + 1)^2
+        assert_eq!(roots(&[2.0, 4.0, 2.0]), vec![Complex64::new(-1.0, 0.0); 2]);
+        assert_eq!(roots(&[1.0, 0.0, 1.0]), vec![Complex64::new(0.0, 1.0), Complex64::new(0.0, -1.0)]);
+        assert_eq!(roots(&[0.0, 2.0, 1.0]), vec![Complex64::new(-0.5, 0.0)]);
+    }
+
+    #[test]
+    fn test_from_roots() {
+        let coeffs = from_roots(&[Complex64::new(1.0, 0.0), Complex64::new(-2.0, 0.0)]);
+        let coeffs: Vec<f64> = coeffs.iter().map(|c| c.re).collect();
+        assert_eq!(coeffs, vec![1.0, 1.0, -2.0]);
+    }
+}
@@ -0,0 +1,368 @@
+//! Equiripple FIR filter design with the Parks-McClellan algorithm
+//!
+//! The Remez exchange algorithm finds linear phase filter which minimizes maximum weighted
+//! error between desired and actual frequency response in the given bands.
+//! Compared to the window method it gives much shorter filters for sharp transition bands.
+//!
+//! Example
+//!
+//! ```
+//! use dsp::filter::fir::FirFilter;
+//! use dsp::filter::design::remez::remez;
+//!
+//! // Lowpass: pass band 0-3kHz, stop band 4-24kHz.
+//! // Error in the stop band is 10 times more important then in the pass band
+//! let taps = remez(61, &[(0.0, 3_000.0), (4_000.0, 24_000.0)], &[1.0, 0.0], &[1.0, 10.0], 48_000).unwrap();
+//! let filter: FirFilter<f32> = FirFilter::new(&taps).unwrap();
+//! ```
+
+use std::f64::consts::PI;
+use crate::{Error, Result};
+
+
+/// Number of grid points per extremal frequency
+const GRID_DENSITY: usize = 16;
+/// Maximum number of exchange iterations
+const MAX_ITERATIONS: usize = 40;
+/// Relative difference between extremal errors at which algorithm is converged
+const TOLERANCE: f64 = 1e-4;
+
+
+/// Design equiripple linear phase FIR filter
+///   * num_taps - Filter length
+///   * bands - Frequency bands (start, end) in Hz. Bands must be increasing and not overlap.
+///   * desired - Desired gain in each band
+///   * weights - Relative weight of the error in each band
+///   * sample_rate - Number of samples/s
+///
+/// Returns error if the specification is invalid or the algorithm doesn't converge.
+/// Filter with even number of taps always has zero at the Nyquist frequency.
+pub fn remez(num_taps: usize, bands: &[(f32, f32)], desired: &[f32], weights: &[f32], sample_rate: usize) -> Result<Vec<f32>> {
+    exchange(num_taps, bands, desired, weights, sample_rate, MAX_ITERATIONS)
+}
+
+/// Remez exchange with the given limit of iterations
+fn exchange(num_taps: usize, bands: &[(f32, f32)], desired: &[f32], weights: &[f32], sample_rate: usize,
+            max_iterations: usize) -> Result<Vec<f32>> {
+    check_spec(num_taps, bands, desired, weights, sample_rate)?;
+    let bands: Vec<(f64, f64)> = bands.iter()
+        .map(|(l, h)| (*l as f64 / sample_rate as f64, *h as f64 / sample_rate as f64))
+        .collect();
+    let desired: Vec<f64> = desired.iter().map(|&d| d as f64).collect();
+    let weights: Vec<f64> = weights.iter().map(|&w| w as f64).collect();
+
+    let r = num_taps / 2 + num_taps % 2;
+    let mut grid = DenseGrid::new(num_taps, r, &bands, &desired, &weights);
+    if grid.freq.len() <= r {
+        return Err(Error::Config("Bands are too narrow for the given number of taps".to_string()));
+    }
+    // Even length filter has factor cos(πf) in its frequency response
+    if num_taps % 2 == 0 {
+        for i in 0..grid.freq.len() {
+            let c = (PI * grid.freq[i]).cos();
+            grid.desired[i] /= c;
+            grid.weights[i] *= c;
+        }
+    }
+
+    let size = grid.freq.len();
+    let mut ext: Vec<usize> = (0..=r).map(|i| i * (size - 1) / r).collect();
+    let mut error = vec![0.0; size];
+    let mut converged = false;
+    for _ in 0..max_iterations {
+        let interp = Interpolator::new(&ext, &grid);
+        for (i, e) in error.iter_mut().enumerate() {
+            *e = grid.weights[i] * (grid.desired[i] - interp.eval(grid.freq[i]));
+        }
+        ext = search(r, &error)?;
+        let max_err = ext.iter().map(|&i| error[i].abs()).fold(0.0, f64::max);
+        let min_err = ext.iter().map(|&i| error[i].abs()).fold(f64::INFINITY, f64::min);
+        if max_err > 0.0 && (max_err - min_err) / max_err < TOLERANCE {
+            converged = true;
+            break;
+        }
+    }
+    if !converged {
+        return Err(Error::Config(format!("Remez algorithm didn't converge in {} iterations", max_iterations)));
+    }
+
+    // Sample the frequency response and convert it into impulse response
+    let interp = Interpolator::new(&ext, &grid);
+    let amplitudes: Vec<f64> = (0..=num_taps / 2)
+        .map(|i| {
+            let f = i as f64 / num_taps as f64;
+            let c = if num_taps % 2 == 0 { (PI * f).cos() } else { 1.0 };
+            interp.eval(f) * c
+        })
+        .collect();
+    Ok(freq_sample(num_taps, &amplitudes))
+}
+
+
+fn check_spec(num_taps: usize, bands: &[(f32, f32)], desired: &[f32], weights: &[f32], sample_rate: usize) -> Result<()> {
+    if num_taps < 3 {
+        return Err(Error::Config("Number of taps must be at least 3".to_string()));
+    }
+    if bands.is_empty() || bands.len() != desired.len() || bands.len() != weights.len() {
+        return Err(Error::Config("Each band requires desired gain and weight".to_string()));
+    }
+    let nyquist = sample_rate as f32 / 2.0;
+    let mut last = 0.0;
+    for (i, (low, high)) in bands.iter().enumerate() {
+        if *low < last || (i > 0 && *low == last) || low >= high || *high > nyquist {
+            return Err(Error::Config(format!("Invalid band ({}, {}). Bands must be increasing and between 0 and {}", low, high, nyquist)));
+        }
+        last = *high;
+    }
+    if weights.iter().any(|w| *w <= 0.0) {
+        return Err(Error::Config("Weights must be positive".to_string()));
+    }
+    Ok(())
+}
+
+
+/// Frequency grid over all bands, with desired response and weight at each point.
+/// Frequencies are normalized to the sample rate (0..0.5)
+struct DenseGrid {
+    freq: Vec<f64>,
+    desired: Vec<f64>,
+    weights: Vec<f64>,
+}
+
+impl DenseGrid {
+    fn new(num_taps: usize, r: usize, bands: &[(f64, f64)], desired: &[f64], weights: &[f64]) -> DenseGrid {
+        let delf = 0.5 / (GRID_DENSITY * r) as f64;
+        let mut grid = DenseGrid { freq: vec![], desired: vec![], weights: vec![] };
+        for (b, (low, high)) in bands.iter().enumerate() {
+            let k = (((high - low) / delf).round() as usize).max(1);
+            for i in 0..k {
+                grid.freq.push(low + i as f64 * delf);
+                grid.desired.push(desired[b]);
+                grid.weights.push(weights[b]);
+            }
+            *grid.freq.last_mut().unwrap() = *high;
+        }
+        // Even length filter can't have any response at the Nyquist frequency
+        if num_taps % 2 == 0 {
+            let last = grid.freq.last_mut().unwrap();
+            if *last > 0.5 - delf {
+                *last = 0.5 - delf;
+            }
+        }
+        grid
+    }
+}
+
+
+/// Barycentric Lagrange interpolation of the best approximation through the extremal points
+struct Interpolator {
+    x: Vec<f64>,
+    ad: Vec<f64>,
+    y: Vec<f64>,
+}
+
+impl Interpolator {
+    fn new(ext: &[usize], grid: &DenseGrid) -> Interpolator {
+        let r = ext.len() - 1;
+        let x: Vec<f64> = ext.iter().map(|&e| (2.0 * PI * grid.freq[e]).cos()).collect();
+        // Products are interleaved to avoid overflow
+        let ld = (r - 1) / 15 + 1;
+        let ad: Vec<f64> = (0..=r)
+            .map(|i| {
+                let mut denom = 1.0;
+                for j in 0..ld {
+                    for k in (j..=r).step_by(ld) {
+                        if k != i {
+                            denom *= 2.0 * (x[i] - x[k]);
+                        }
+                    }
+                }
+                if denom.abs() < 1e-5 { denom = 1e-5; }
+                1.0 / denom
+            })
+            .collect();
+
+        // Deviation which makes weighted error alternate at extremal points
+        let mut numer = 0.0;
+        let mut denom = 0.0;
+        let mut sign = 1.0;
+        for i in 0..=r {
+            numer += ad[i] * grid.desired[ext[i]];
+            denom += sign * ad[i] / grid.weights[ext[i]];
+            sign = -sign;
+        }
+        let delta = numer / denom;
+        let mut sign = 1.0;
+        let y = (0..=r)
+            .map(|i| {
+                let v = grid.desired[ext[i]] - sign * delta / grid.weights[ext[i]];
+                sign = -sign;
+                v
+            })
+            .collect();
+        Interpolator { x, ad, y }
+    }
+
+    fn eval(&self, freq: f64) -> f64 {
+        let xc = (2.0 * PI * freq).cos();
+        let mut numer = 0.0;
+        let mut denom = 0.0;
+        for i in 0..self.x.len() {
+            let c = xc - self.x[i];
+            if c.abs() < 1e-7 {
+                return self.y[i];
+            }
+            let c = self.ad[i] / c;
+            denom += c;
+            numer += c * self.y[i];
+        }
+        numer / denom
+    }
+}
+
+
+/// Find r+1 alternating extrema of the error function
+fn search(r: usize, error: &[f64]) -> Result<Vec<usize>> {
+    let size = error.len();
+    let mut found = vec![];
+    if (error[0] > 0.0 && error[0] > error[1]) || (error[0] < 0.0 && error[0] < error[1]) {
+        found.push(0);
+    }
+    for i in 1..size - 1 {
+        if (error[i] >= error[i - 1] && error[i] > error[i + 1] && error[i] > 0.0)
+            || (error[i] <= error[i - 1] && error[i] < error[i + 1] && error[i] < 0.0) {
+            found.push(i);
+        }
+    }
+    let j = size - 1;
+    if (error[j] > 0.0 && error[j] > error[j - 1]) || (error[j] < 0.0 && error[j] < error[j - 1]) {
+        found.push(j);
+    }
+    if found.len() <= r {
+        return Err(Error::Config("Remez algorithm failed to find enough extremal frequencies".to_string()));
+    }
+
+    // Remove extra extrema, preferring the ones which break alternation
+    let mut extra = found.len() - (r + 1);
+    while extra > 0 {
+        let k = found.len();
+        let mut up = error[found[0]] > 0.0;
+        let mut l = 0;
+        let mut alternating = true;
+        for j in 1..k {
+            if error[found[j]].abs() < error[found[l]].abs() {
+                l = j;
+            }
+            if up && error[found[j]] < 0.0 {
+                up = false;
+            } else if !up && error[found[j]] > 0.0 {
+                up = true;
+            } else {
+                alternating = false;
+                break;
+            }
+        }
+        if alternating && extra == 1 {
+            l = if error[found[k - 1]].abs() < error[found[0]].abs() { k - 1 } else { 0 };
+        }
+        found.remove(l);
+        extra -= 1;
+    }
+    Ok(found)
+}
+
+
+/// Impulse response of the symmetric filter from the samples of its amplitude response
+fn freq_sample(num_taps: usize, amplitudes: &[f64]) -> Vec<f32> {
+    let n = num_taps as f64;
+    let m = (n - 1.0) / 2.0;
+    let last = if num_taps % 2 == 0 { num_taps / 2 - 1 } else { num_taps / 2 };
+    (0..num_taps)
+        .map(|i| {
+            let x = 2.0 * PI * (i as f64 - m) / n;
+            let v: f64 = amplitudes[0] + (1..=last).map(|k| 2.0 * amplitudes[k] * (x * k as f64).cos()).sum::<f64>();
+            (v / n) as f32
+        })
+        .collect()
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use super::*;
+
+    const SAMPLE_RATE: usize = 8_000;
+
+    /// Filter gain at the given frequency
+    fn gain(taps: &[f32], freq: f32) -> f64 {
+        let w = 2.0 * PI * freq as f64 / SAMPLE_RATE as f64;
+        let (re, im) = taps.iter().enumerate()
+            .fold((0.0, 0.0), |(re, im), (i, h)| {
+                (re + *h as f64 * (w * i as f64).cos(), im - *h as f64 * (w * i as f64).sin())
+            });
+        (re * re + im * im).sqrt()
+    }
+
+    /// Maximum deviation from the desired gain in the band
+    fn max_error(taps: &[f32], band: (f32, f32), desired: f64) -> f64 {
+        (0..=100)
+            .map(|i| band.0 + (band.1 - band.0) * i as f32 / 100.0)
+            .map(|f| (gain(taps, f) - desired).abs())
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn test_lowpass() {
+        let bands = [(0.0, 1_000.0), (1_500.0, 4_000.0)];
+        let taps = remez(51, &bands, &[1.0, 0.0], &[1.0, 1.0], SAMPLE_RATE).unwrap();
+        assert_eq!(taps.len(), 51);
+        for i in 0..25 {
+            assert_approx_eq!(taps[i], taps[50 - i], 1e-6f32);
+        }
+        let pass = max_error(&taps, bands[0], 1.0);
+        let stop = max_error(&taps, bands[1], 0.0);
+        // Equiripple: the same error in both bands
+        assert!(pass < 0.002, "Pass band error: {}", pass);
+        assert_approx_eq!(pass, stop, 0.1 * pass);
+    }
+
+    #[test]
+    fn test_weights() {
+        let bands = [(0.0, 1_000.0), (1_500.0, 4_000.0)];
+        let taps = remez(40, &bands, &[1.0, 0.0], &[1.0, 10.0], SAMPLE_RATE).unwrap();
+        assert_eq!(taps.len(), 40);
+        let pass = max_error(&taps, bands[0], 1.0);
+        let stop = max_error(&taps, bands[1], 0.0);
+        assert_approx_eq!(pass / stop, 10.0, 1.0);
+    }
+
+    #[test]
+    fn test_bandpass() {
+        let bands = [(0.0, 800.0), (1_000.0, 2_000.0), (2_200.0, 4_000.0)];
+        let taps = remez(101, &bands, &[0.0, 1.0, 0.0], &[1.0, 1.0, 1.0], SAMPLE_RATE).unwrap();
+        assert!(max_error(&taps, bands[1], 1.0) < 0.02);
+        assert!(gain(&taps, 400.0) < 0.02);
+        assert!(gain(&taps, 3_000.0) < 0.02);
+    }
+
+    #[test]
+    fn test_invalid_spec() {
+        assert!(remez(51, &[(0.0, 1_000.0), (900.0, 4_000.0)], &[1.0, 0.0], &[1.0, 1.0], SAMPLE_RATE).is_err());
+        assert!(remez(51, &[(0.0, 1_000.0), (1_500.0, 5_000.0)], &[1.0, 0.0], &[1.0, 1.0], SAMPLE_RATE).is_err());
+        assert!(remez(51, &[(0.0, 1_000.0)], &[1.0, 0.0], &[1.0], SAMPLE_RATE).is_err());
+        assert!(remez(51, &[(0.0, 1_000.0)], &[1.0], &[0.0], SAMPLE_RATE).is_err());
+    }
+
+    #[test]
+    fn test_not_converged() {
+        let bands = [(0.0, 1_000.0), (1_500.0, 4_000.0)];
+        // Initial extremal frequencies are far from the equiripple solution
+        let result = exchange(51, &bands, &[1.0, 0.0], &[1.0, 100.0], SAMPLE_RATE, 1);
+        assert!(matches!(result, Err(Error::Config(msg)) if msg.contains("didn't converge")));
+        assert!(exchange(51, &bands, &[1.0, 0.0], &[1.0, 100.0], SAMPLE_RATE, MAX_ITERATIONS).is_ok());
+    }
+}
@@ -0,0 +1,248 @@
+//! Zero-phase forward-backward filtering
+//!
+//! The signal is filtered forward and then backward, so the phase distortion of the filter
+//! is cancelled and the magnitude response is squared. The whole signal must be available,
+//! so this is an offline operation.
+//!
+//! To reduce the transients, the signal is extended at both ends and the filter state is
+//! initialized with the steady state response for the first sample (like SciPy's `filtfilt`).
+//!
+//! Example
+//!
+//! ```
+//! use dsp::filter::design::iir::{self, Band};
+//! use dsp::filter::filtfilt::{filtfilt, Padding};
+//!
+//! let filter = iir::butterworth(2, Band::Lowpass(5.0), 100).unwrap();
+//! let signal: Vec<f32> = (0..500).map(|i| (i as f32 * 0.05).sin() + 0.1 * (i as f32 * 2.0).sin()).collect();
+//! let smooth = filtfilt(&filter, &signal, Padding::Odd).unwrap();
+//! assert_eq!(smooth.len(), signal.len());
+//! ```
+
+use num_traits::{cast, Zero};
+use crate::{Error, Result};
+use crate::filter::analysis::FilterResponse;
+use crate::sample::Sample;
+
+
+/// Signal extension at the edges
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Padding {
+    /// Point reflection around the edge sample: 2*x[0] - x[n]
+    #[default]
+    Odd,
+    /// Mirror reflection: x[n]
+    Even,
+    /// Repeat the edge sample
+    Constant,
+    /// No extension
+    None,
+}
+
+
+/// Filter the signal forward and backward.
+/// The signal is extended by 3 * (filter order + 1) samples at each end.
+pub fn filtfilt<F, T>(filter: &F, input: &[T], padding: Padding) -> Result<Vec<T>>
+where
+    F: FilterResponse + ?Sized,
+    T: Sample,
+{
+    let order: usize = filter.transfer_function().iter()
+        .map(|(b, a)| usize::max(b.len(), a.len()) - 1)
+        .sum();
+    filtfilt_padded(filter, input, padding, 3 * (order + 1))
+}
+
+/// Filter the signal forward and backward with the given extension length at each end.
+/// The signal must be longer then the extension.
+pub fn filtfilt_padded<F, T>(filter: &F, input: &[T], padding: Padding, pad_len: usize) -> Result<Vec<T>>
+where
+    F: FilterResponse + ?Sized,
+    T: Sample,
+{
+    let pad_len = if padding == Padding::None { 0 } else { pad_len };
+    if input.is_empty() || (pad_len > 0 && input.len() <= pad_len) {
+        return Err(Error::Config(format!("Signal length {} must be greater then padding length {}", input.len(), pad_len)));
+    }
+    let sections = filter.transfer_function().iter()
+        .map(|(b, a)| Section::new(b, a))
+        .collect::<Result<Vec<Section<T>>>>()?;
+
+    let mut signal = extend(input, padding, pad_len);
+    filter_with_initial_state(&sections, &mut signal);
+    signal.reverse();
+    filter_with_initial_state(&sections, &mut signal);
+    signal.reverse();
+    Ok(signal[pad_len..pad_len + input.len()].to_vec())
+}
+
+/// Initial state of the transposed direct form II filter (b, a),
+/// which corresponds to the steady state of the step response.
+/// Multiply it by the first sample to start filtering without the transient.
+pub fn lfilter_zi(b: &[f64], a: &[f64]) -> Result<Vec<f64>> {
+    if a.is_empty() || a[0] == 0.0 {
+        return Err(Error::Config("Coefficient a0 can't be 0".to_string()));
+    }
+    let n = usize::max(a.len(), b.len());
+    let coeff = |xs: &[f64], i: usize| xs.get(i).cloned().unwrap_or(0.0) / a[0];
+    let a_sum: f64 = (0..n).map(|i| coeff(a, i)).sum();
+    if a_sum == 0.0 {
+        return Err(Error::Config("Filter with pole at z=1 doesn't have steady state".to_string()));
+    }
+    // DC gain
+    let gain = (0..n).map(|i| coeff(b, i)).sum::<f64>() / a_sum;
+    // z[k] = sum_{j>k} (b[j] - a[j] * gain)
+    let mut zi = vec![0.0; n - 1];
+    let mut acc = 0.0;
+    for k in (0..n - 1).rev() {
+        acc += coeff(b, k + 1) - coeff(a, k + 1) * gain;
+        zi[k] = acc;
+    }
+    Ok(zi)
+}
+
+
+/// Single transfer function in transposed direct form II
+struct Section<T: Sample> {
+    b: Vec<T::Real>,
+    a: Vec<T::Real>,
+    zi: Vec<T::Real>,
+    gain: T::Real,
+}
+
+impl<T: Sample> Section<T> {
+    fn new(b: &[f64], a: &[f64]) -> Result<Section<T>> {
+        let zi = lfilter_zi(b, a)?;
+        let n = zi.len() + 1;
+        let norm = |xs: &[f64]| -> Vec<T::Real> {
+            (0..n).map(|i| cast(xs.get(i).cloned().unwrap_or(0.0) / a[0]).unwrap()).collect()
+        };
+        let gain = b.iter().sum::<f64>() / a.iter().sum::<f64>();
+        Ok(Section {
+            b: norm(b),
+            a: norm(a),
+            zi: zi.iter().map(|z| cast(*z).unwrap()).collect(),
+            gain: cast(gain).unwrap(),
+        })
+    }
+}
+
+/// Filter the signal in place with the cascade of sections.
+/// The state of each section is initialized to the steady state for the first sample.
+fn filter_with_initial_state<T: Sample>(sections: &[Section<T>], signal: &mut [T]) {
+    let mut x0 = signal[0];
+    for s in sections {
+        let n = s.b.len();
+        let mut z: Vec<T> = s.zi.iter().map(|zi| x0 * *zi).collect();
+        for x in signal.iter_mut() {
+            let input = *x;
+            let y = input * s.b[0] + z.first().cloned().unwrap_or_default();
+            for k in 0..n - 1 {
+                let next = if k + 1 < n - 1 { z[k + 1] } else { T::default() };
+                z[k] = input * s.b[k + 1] - y * s.a[k + 1] + next;
+            }
+            *x = y;
+        }
+        // Input of the next section starts at the steady state of this one
+        x0 = x0 * s.gain;
+        if s.gain.is_zero() {
+            x0 = T::default();
+        }
+    }
+}
+
+/// Extend signal at both ends
+fn extend<T: Sample>(input: &[T], padding: Padding, pad_len: usize) -> Vec<T> {
+    let n = input.len();
+    let (first, last) = (input[0], input[n - 1]);
+    let two: T::Real = cast(2.0).unwrap();
+    let left = (1..=pad_len).rev().map(|i| match padding {
+        Padding::Odd => first * two - input[i],
+        Padding::Even => input[i],
+        _ => first,
+    });
+    let right = (1..=pad_len).map(|i| match padding {
+        Padding::Odd => last * two - input[n - 1 - i],
+        Padding::Even => input[n - 1 - i],
+        _ => last,
+    });
+    left.chain(input.iter().cloned()).chain(right).collect()
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use crate::filter::biquad::BiquadFilter;
+    use crate::filter::design::iir::{self, Band, Prototype};
+    use crate::filter::fir::FirFilter;
+    use crate::filter::sos::SosFilter;
+    use crate::runtime::node::ProcessNode;
+    use super::*;
+
+    #[test]
+    fn test_lfilter_zi() {
+        // Filtering constant signal starting from zi gives constant output
+        let zpk = iir::digital(Prototype::Butterworth, 2, Band::Lowpass(1_000.0), 8_000).unwrap();
+        let (b, a) = SosFilter::<f64>::from_zpk(&zpk).unwrap().to_tf();
+        let zi = lfilter_zi(&b, &a).unwrap();
+        let section: Section<f64> = Section::new(&b, &a).unwrap();
+        assert_eq!(section.zi, zi);
+        let mut signal = vec![2.0; 20];
+        filter_with_initial_state(&[section], &mut signal);
+        for y in signal {
+            assert_approx_eq!(y, 2.0, 1e-12);
+        }
+        assert!(lfilter_zi(&[1.0], &[1.0, -1.0]).is_err());
+    }
+
+    #[test]
+    fn test_zero_phase() {
+        // Low frequency sine passes without delay
+        let filter = iir::butterworth(4, Band::Lowpass(10.0), 1_000).unwrap();
+        let input: Vec<f32> = (0..2_000).map(|i| (2.0 * std::f32::consts::PI * i as f32 / 1_000.0).sin()).collect();
+        for padding in [Padding::Odd, Padding::Even, Padding::Constant] {
+            let output = filtfilt(&filter, &input, padding).unwrap();
+            for i in 500..1_500 {
+                assert_approx_eq!(output[i], input[i], 1e-3);
+            }
+        }
+        // Single pass through the same filter has a delay
+        let mut output = vec![0.0; 2_000];
+        let mut sos: SosFilter = SosFilter::from_biquads(&filter).unwrap();
+        sos.process_buffer(&input, &mut output).unwrap();
+        assert!((output[1_000] - input[1_000]).abs() > 0.1);
+    }
+
+    #[test]
+    fn test_fir_and_constant_signal() {
+        let filter: FirFilter = FirFilter::new(&[0.2, 0.3, 0.5]).unwrap();
+        let output = filtfilt(&filter, &[3.0f32; 30], Padding::Odd).unwrap();
+        for y in output {
+            assert_approx_eq!(y, 3.0, 1e-6);
+        }
+        let filter: BiquadFilter = BiquadFilter::new(&[0.5, 0.0, 0.0], &[1.0, -0.5, 0.0]).unwrap();
+        let output = filtfilt(&filter, &[1.0f32; 30], Padding::None).unwrap();
+        for y in output {
+            assert_approx_eq!(y, 1.0, 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_extend() {
+        let input = [1.0f32, 2.0, 4.0, 7.0];
+        assert_eq!(extend(&input, Padding::Odd, 2), vec![-2.0, 0.0, 1.0, 2.0, 4.0, 7.0, 10.0, 12.0]);
+        assert_eq!(extend(&input, Padding::Even, 2), vec![4.0, 2.0, 1.0, 2.0, 4.0, 7.0, 4.0, 2.0]);
+        assert_eq!(extend(&input, Padding::Constant, 2), vec![1.0, 1.0, 1.0, 2.0, 4.0, 7.0, 7.0, 7.0]);
+    }
+
+    #[test]
+    fn test_signal_too_short() {
+        let filter = iir::butterworth(4, Band::Lowpass(10.0), 1_000).unwrap();
+        assert!(filtfilt(&filter, &[1.0f32; 10], Padding::Odd).is_err());
+        assert!(filtfilt(&filter, &[1.0f32; 10], Padding::None).is_ok());
+    }
+}
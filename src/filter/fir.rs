@@ -0,0 +1,133 @@
+//! Finite impulse response filter
+//! 
+//! y[n] = sum_k h[k] * x[n-k]
+//! 
+//! Taps can be designed with the functions from the `filter::design` module.
+
+use crate::{Error, Result};
+use crate::runtime::node::ProcessNode;
+use crate::sample::Sample;
+
+
+/// FIR filter for real or complex samples with real taps.
+/// Filter keeps the last samples, so the signal can be processed in multiple buffers.
+/// 
+/// Example
+/// 
+/// ```
+/// use dsp::runtime::node::ProcessNode;
+/// use dsp::filter::fir::FirFilter;
+/// 
+/// // Moving average
+/// let mut filter = FirFilter::new(&[0.5, 0.5]).unwrap();
+/// let input_buffer = vec![2.0, 4.0, 6.0];
+/// let mut output_buffer = vec![0.0; 3];
+/// filter.process_buffer(&input_buffer, &mut output_buffer).unwrap();
+/// 
+/// assert_eq!(output_buffer, vec![1.0, 3.0, 5.0]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct FirFilter<T: Sample = f32> {
+    // Taps in reversed order
+    taps: Vec<T::Real>,
+    // Delay line stored twice, so the last N samples are always in one continuous slice
+    history: Vec<T>,
+    pos: usize,
+}
+
+impl<T: Sample> FirFilter<T> {
+    /// Create new filter with given taps. Returns error if there are no taps
+    pub fn new(taps: &[T::Real]) -> Result<FirFilter<T>> {
+        if taps.is_empty() {
+            return Err(Error::Config("FIR filter requires at least one tap".to_string()));
+        }
+        let taps: Vec<T::Real> = taps.iter().rev().cloned().collect();
+        let history = vec![T::default(); 2 * taps.len()];
+        Ok(FirFilter { taps, history, pos: 0 })
+    }
+
+    /// Filter taps
+    pub fn taps(&self) -> Vec<T::Real> {
+        self.taps.iter().rev().cloned().collect()
+    }
+
+    /// Clear filter state
+    pub fn reset(&mut self) {
+        self.history.fill(T::default());
+        self.pos = 0;
+    }
+
+    /// Process single sample
+    pub fn process_sample(&mut self, x: T) -> T {
+        let n = self.taps.len();
+        self.pos = (self.pos + 1) % n;
+        self.history[self.pos] = x;
+        self.history[self.pos + n] = x;
+        let window = &self.history[self.pos + 1..self.pos + 1 + n];
+        window.iter().zip(self.taps.iter())
+            .fold(T::default(), |acc, (x, h)| acc + *x * *h)
+    }
+}
+
+impl<T: Sample> ProcessNode<T, T> for FirFilter<T> {
+    fn process_buffer(&mut self, input_buffer: &[T], output_buffer: &mut [T]) -> Result<usize> {
+        let n = usize::min(input_buffer.len(), output_buffer.len());
+        for i in 0..n {
+            output_buffer[i] = self.process_sample(input_buffer[i]);
+        }
+        Ok(n)
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use num_complex::Complex32;
+    use super::*;
+
+    #[test]
+    fn test_impulse_response() {
+        let taps = [1.0, 2.0, 3.0];
+        let mut filter = FirFilter::new(&taps).unwrap();
+        let input_buffer = vec![1.0, 0.0, 0.0, 0.0, 0.0];
+        let mut output_buffer = vec![0.0; 5];
+        filter.process_buffer(&input_buffer, &mut output_buffer).unwrap();
+        assert_eq!(output_buffer, vec![1.0, 2.0, 3.0, 0.0, 0.0]);
+        assert_eq!(filter.taps(), taps.to_vec());
+    }
+
+    #[test]
+    fn test_streaming() {
+        let taps = [0.1, -0.4, 0.7, 0.2];
+        let input: Vec<f32> = (0..20).map(|i| (i as f32 * 0.3).sin()).collect();
+        let mut expected = vec![0.0; 20];
+        FirFilter::new(&taps).unwrap().process_buffer(&input, &mut expected).unwrap();
+
+        let mut filter = FirFilter::new(&taps).unwrap();
+        let mut output = [0.0; 20];
+        for (inp, out) in input.chunks(3).zip(output.chunks_mut(3)) {
+            filter.process_buffer(inp, out).unwrap();
+        }
+        for i in 0..20 {
+            assert_approx_eq!(output[i], expected[i], 1e-6f32);
+        }
+    }
+
+    #[test]
+    fn test_complex() {
+        let mut filter = FirFilter::new(&[0.5, 0.5]).unwrap();
+        let input_buffer = vec![Complex32::new(2.0, -2.0), Complex32::new(4.0, 0.0)];
+        let mut output_buffer = vec![Complex32::default(); 2];
+        filter.process_buffer(&input_buffer, &mut output_buffer).unwrap();
+        assert_eq!(output_buffer, vec![Complex32::new(1.0, -1.0), Complex32::new(3.0, -1.0)]);
+    }
+
+    #[test]
+    fn test_no_taps() {
+        assert!(matches!(FirFilter::<f32>::new(&[]), Err(Error::Config(_))));
+    }
+}
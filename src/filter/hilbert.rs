@@ -0,0 +1,220 @@
+//! Hilbert transform and analytic signal
+//!
+//! Analytic signal x + j*H{x} has only positive frequencies. Its magnitude is the envelope
+//! of the signal and the derivative of its phase is the instantaneous frequency.
+//!
+//!   * Hilbert - streaming FIR Hilbert transformer
+//!   * analytic_signal - FFT based transform of the whole buffer
+//!
+//! Example
+//!
+//! ```
+//! use dsp::filter::hilbert::{analytic_signal, envelope, instantaneous_frequency};
+//!
+//! // 50Hz tone with slow amplitude modulation
+//! let signal: Vec<f32> = (0..1_000)
+//!     .map(|i| i as f32 / 1_000.0)
+//!     .map(|t| (1.0 + 0.5 * (2.0 * std::f32::consts::PI * 2.0 * t).cos()) * (2.0 * std::f32::consts::PI * 50.0 * t).cos())
+//!     .collect();
+//! let analytic = analytic_signal(&signal);
+//! let env = envelope(&analytic);
+//! let freq = instantaneous_frequency(&analytic, 1_000);
+//! assert!((env[0] - 1.5).abs() < 0.01);
+//! assert!((freq[500] - 50.0).abs() < 0.1);
+//! ```
+
+use std::collections::VecDeque;
+use num_complex::Complex;
+use num_traits::{cast, Float, Zero};
+use rustfft::{FftNum, FftPlanner};
+use crate::{Error, Result};
+use crate::filter::analysis::unwrap;
+use crate::filter::design::fir;
+use crate::filter::fir::FirFilter;
+use crate::runtime::node::ProcessNode;
+use crate::sample::Sample;
+use crate::window;
+
+
+/// Streaming Hilbert transformer. Converts real signal into analytic signal.
+/// Real part is delayed by (N-1)/2 samples to match the delay of the FIR filter.
+///
+/// Example
+///
+/// ```
+/// use dsp::runtime::node::ProcessNode;
+/// use dsp::filter::hilbert::Hilbert;
+/// use dsp::num_complex::Complex32;
+///
+/// let mut hilbert: Hilbert = Hilbert::new(65).unwrap();
+/// assert_eq!(hilbert.delay(), 32);
+/// let input: Vec<f32> = (0..200).map(|i| (0.5 * i as f32).cos()).collect();
+/// let mut output = vec![Complex32::new(0.0, 0.0); 200];
+/// hilbert.process_buffer(&input, &mut output).unwrap();
+///
+/// // Envelope of the cosine
+/// assert!((output[100].norm() - 1.0).abs() < 0.01);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Hilbert<T: Sample<Real = T> + Float = f32> {
+    filter: FirFilter<T>,
+    delay_line: VecDeque<T>,
+}
+
+impl<T: Sample<Real = T> + Float> Hilbert<T> {
+    /// Create Hilbert transformer with Blackman window
+    ///   * num_taps - Filter length, has to be odd. Longer filter works closer to 0 and Nyquist frequency
+    pub fn new(num_taps: usize) -> Result<Hilbert<T>> {
+        let taps = fir::hilbert(num_taps, window::blackman)?;
+        let taps: Vec<T::Real> = taps.iter().map(|t| cast(*t).unwrap()).collect();
+        Hilbert::with_taps(&taps)
+    }
+
+    /// Create Hilbert transformer with the given taps, e.g. from `fir::hilbert`.
+    /// Number of taps has to be odd, so the delay is a whole number of samples.
+    pub fn with_taps(taps: &[T::Real]) -> Result<Hilbert<T>> {
+        if taps.len() % 2 == 0 {
+            return Err(Error::Config("Hilbert transformer requires odd number of taps".to_string()));
+        }
+        let delay = (taps.len() - 1) / 2;
+        Ok(Hilbert {
+            filter: FirFilter::new(taps)?,
+            delay_line: vec![T::default(); delay].into(),
+        })
+    }
+
+    /// Delay of the output in samples
+    pub fn delay(&self) -> usize {
+        self.delay_line.len()
+    }
+
+    /// Clear filter state
+    pub fn reset(&mut self) {
+        self.filter.reset();
+        self.delay_line.iter_mut().for_each(|x| *x = T::default());
+    }
+
+    /// Process single sample
+    pub fn process_sample(&mut self, x: T) -> Complex<T> {
+        self.delay_line.push_back(x);
+        let re = self.delay_line.pop_front().unwrap_or(x);
+        Complex::new(re, self.filter.process_sample(x))
+    }
+}
+
+impl<T: Sample<Real = T> + Float> ProcessNode<T, Complex<T>> for Hilbert<T> {
+    fn process_buffer(&mut self, input_buffer: &[T], output_buffer: &mut [Complex<T>]) -> Result<usize> {
+        let n = usize::min(input_buffer.len(), output_buffer.len());
+        for i in 0..n {
+            output_buffer[i] = self.process_sample(input_buffer[i]);
+        }
+        Ok(n)
+    }
+}
+
+
+/// Analytic signal of the whole buffer computed with FFT.
+/// Negative frequencies are removed and positive frequencies are doubled.
+pub fn analytic_signal<R: Float + FftNum>(input: &[R]) -> Vec<Complex<R>> {
+    let n = input.len();
+    if n == 0 {
+        return vec![];
+    }
+    let mut planner = FftPlanner::new();
+    let mut buffer: Vec<Complex<R>> = input.iter().map(|x| Complex::new(*x, R::zero())).collect();
+    planner.plan_fft_forward(n).process(&mut buffer);
+    // DC and Nyquist bins are kept, negative frequencies removed
+    let two: R = cast(2.0).unwrap();
+    for (i, c) in buffer.iter_mut().enumerate().skip(1) {
+        if 2 * i < n {
+            *c = *c * two;
+        } else if 2 * i > n {
+            *c = Complex::zero();
+        }
+    }
+    planner.plan_fft_inverse(n).process(&mut buffer);
+    let scale: R = cast::<usize, R>(n).unwrap().recip();
+    buffer.iter().map(|c| *c * scale).collect()
+}
+
+/// Envelope (magnitude) of the analytic signal
+pub fn envelope<R: Float>(analytic: &[Complex<R>]) -> Vec<R> {
+    analytic.iter().map(|c| c.norm()).collect()
+}
+
+/// Unwrapped phase of the analytic signal in radians
+pub fn instantaneous_phase<R: Float>(analytic: &[Complex<R>]) -> Vec<R> {
+    let mut phase: Vec<R> = analytic.iter().map(|c| c.arg()).collect();
+    unwrap(&mut phase);
+    phase
+}
+
+/// Instantaneous frequency in Hz. Output has one sample less then the input.
+pub fn instantaneous_frequency<R: Float>(analytic: &[Complex<R>], sample_rate: usize) -> Vec<R> {
+    let scale: R = cast(sample_rate as f64 / (2.0 * std::f64::consts::PI)).unwrap();
+    instantaneous_phase(analytic).windows(2)
+        .map(|w| (w[1] - w[0]) * scale)
+        .collect()
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use assert_approx_eq::assert_approx_eq;
+    use super::*;
+
+    #[test]
+    fn test_streaming_hilbert() {
+        // cos -> exp(jwt) delayed by the filter
+        let w = 0.3;
+        let input: Vec<f64> = (0..300).map(|i| (w * i as f64).cos()).collect();
+        let mut hilbert: Hilbert<f64> = Hilbert::new(101).unwrap();
+        let mut output = vec![Complex::zero(); 300];
+        assert_eq!(hilbert.process_buffer(&input, &mut output).unwrap(), 300);
+        for (i, y) in output.iter().enumerate().skip(150) {
+            let expected = Complex::from_polar(1.0, w * (i - 50) as f64);
+            assert!((y - expected).norm() < 1e-3, "Wrong sample {}: {}", i, y);
+        }
+    }
+
+    #[test]
+    fn test_invalid_taps() {
+        assert!(matches!(Hilbert::<f32>::with_taps(&[]), Err(Error::Config(_))));
+        assert!(matches!(Hilbert::<f32>::with_taps(&[-0.5, 0.5]), Err(Error::Config(_))));
+        assert!(Hilbert::<f32>::with_taps(&[-0.5, 0.0, 0.5]).is_ok());
+    }
+
+    #[test]
+    fn test_analytic_signal() {
+        // Whole number of periods gives exact result
+        let w = 2.0 * PI * 5.0 / 64.0;
+        let input: Vec<f64> = (0..64).map(|i| 2.0 + (w * i as f64).cos()).collect();
+        let analytic = analytic_signal(&input);
+        for (i, c) in analytic.iter().enumerate() {
+            assert_approx_eq!(c.re, input[i], 1e-12);
+            assert_approx_eq!(c.im, (w * i as f64).sin(), 1e-12);
+        }
+        assert!(analytic_signal::<f32>(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_phase_and_frequency() {
+        // Chirp from 10Hz to 30Hz in 1 second
+        let fs = 1_000;
+        let phase_at = |t: f64| 2.0 * PI * (10.0 * t + 10.0 * t * t);
+        let input: Vec<f64> = (0..fs).map(|i| phase_at(i as f64 / fs as f64).cos()).collect();
+        let analytic = analytic_signal(&input);
+        let phase = instantaneous_phase(&analytic);
+        let freq = instantaneous_frequency(&analytic, fs);
+        assert_eq!(freq.len(), fs - 1);
+        for i in 200..800 {
+            let t = i as f64 / fs as f64;
+            assert_approx_eq!(phase[i] - phase[200], phase_at(t) - phase_at(0.2), 0.05);
+            assert_approx_eq!(freq[i], 10.0 + 20.0 * (t + 0.0005), 0.2);
+        }
+    }
+}
@@ -4,17 +4,23 @@
 //! 
 //! x[t] = alpha * x + (1-alpha)*x[t-1]
 
+use num_traits::One;
+use crate::Result;
+use crate::runtime::node::ProcessNode;
+use crate::sample::Sample;
+
+/// Leaky integrator for real or complex samples
 #[derive(Clone,Debug)]
-pub struct LeakyIntegrator {
-    alpha: f32,
-    last_value: f32,
+pub struct LeakyIntegrator<T: Sample = f32> {
+    alpha: T::Real,
+    last_value: T,
 }
 
 
-impl LeakyIntegrator {
+impl<T: Sample> LeakyIntegrator<T> {
 
     /// Create new LeakyIntegrator filter
-    pub fn new(alpha: f32, init_value: f32) -> LeakyIntegrator {
+    pub fn new(alpha: T::Real, init_value: T) -> LeakyIntegrator<T> {
         LeakyIntegrator { alpha, last_value: init_value }
     }
 
@@ -26,22 +32,24 @@ impl LeakyIntegrator {
     /// use assert_approx_eq::assert_approx_eq;
     /// use dsp::filter::leaky::LeakyIntegrator;
     /// 
-    /// let mut filter = LeakyIntegrator::new(0.1, 16.);
+    /// let mut filter: LeakyIntegrator = LeakyIntegrator::new(0.1, 16.);
     /// let out = filter.next_value(10.);
     /// 
     /// assert_approx_eq!(out, 15.4, 1e-5f32);
     /// ```
-    pub fn next_value(&mut self, v: f32) -> f32 {
-        self.last_value = self.alpha*v + (1. - self.alpha)*self.last_value;
+    pub fn next_value(&mut self, v: T) -> T {
+        self.last_value = v*self.alpha + self.last_value*(T::Real::one() - self.alpha);
         self.last_value
     }
+}
 
-
-    pub fn process_buffer(&mut self, input_buffer: &[f32], output_buffer: &mut [f32]) {
+impl<T: Sample> ProcessNode<T, T> for LeakyIntegrator<T> {
+    fn process_buffer(&mut self, input_buffer: &[T], output_buffer: &mut [T]) -> Result<usize> {
         let size = std::cmp::min(input_buffer.len(), output_buffer.len());
         for i in 0..size {
             output_buffer[i] = self.next_value(input_buffer[i]);
         }
+        Ok(size)
     }
 }
 
@@ -65,4 +73,10 @@ mod tests {
         assert_approx_eq!(output_buffer[1], 15.05, 1e-3f32);
         assert_approx_eq!(output_buffer[2], 23.54, 1e-2f32);
     }
+
+    #[test]
+    fn test_f64() {
+        let mut filter = LeakyIntegrator::new(0.1, 15.0f64);
+        assert_approx_eq!(filter.next_value(10.0), 14.5, 1e-12);
+    }
 }
\ No newline at end of file
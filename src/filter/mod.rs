@@ -1,2 +1,10 @@
+pub mod analysis;
 pub mod biquad;
-pub mod leaky;
\ No newline at end of file
+pub mod convolution;
+pub mod design;
+pub mod filtfilt;
+pub mod fir;
+pub mod hilbert;
+pub mod leaky;
+pub mod multirate;
+pub mod sos;
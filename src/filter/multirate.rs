@@ -0,0 +1,621 @@
+//! Sample rate conversion
+//!
+//! All blocks filter the signal with the lowpass FIR filter split into polyphase branches,
+//! so the filter runs at the lower sample rate:
+//!
+//!   * Decimator - anti-alias filter and keep every M-th sample
+//!   * Interpolator - insert L-1 zeros after each sample and remove the images
+//!   * RationalResampler - interpolate by L and decimate by M in one step
+//!   * Resampler - arbitrary ratio, interpolates between the branches of the filter bank.
+//!     Ratio can be adjusted at runtime, e.g. to compensate the clock drift.
+//!
+//! The phase is kept between the calls, so the input can be split into buffers of any length.
+//! Use `ProcessNode::output_size` to find the size of the output buffer.
+//!
+//! Example
+//!
+//! ```
+//! use dsp::runtime::node::ProcessNode;
+//! use dsp::filter::multirate::{Decimator, Interpolator};
+//!
+//! let mut decimator: Decimator = Decimator::new(4).unwrap();
+//! let mut interpolator: Interpolator = Interpolator::new(4).unwrap();
+//! let input = vec![1.0; 10];
+//! let mut low_rate = vec![0.0; decimator.output_size(10)];
+//! let mut high_rate = vec![0.0; interpolator.output_size(3)];
+//!
+//! assert_eq!(decimator.process_buffer(&input, &mut low_rate).unwrap(), 3);
+//! assert_eq!(interpolator.process_buffer(&low_rate, &mut high_rate).unwrap(), 12);
+//! ```
+
+use num_traits::{cast, Float, One};
+use crate::{Error, Result};
+use crate::filter::design::fir;
+use crate::filter::fir::FirFilter;
+use crate::runtime::node::ProcessNode;
+use crate::sample::Sample;
+use crate::window;
+
+
+/// Number of taps per polyphase branch in the default filter
+const TAPS_PER_PHASE: usize = 20;
+
+/// Number of branches in the arbitrary ratio resampler filter bank
+const NUM_PHASES: usize = 64;
+
+
+/// Lowpass filter with the cutoff at Nyquist/factor and Hamming window
+pub fn default_taps(factor: usize) -> Result<Vec<f32>> {
+    check_factor(factor)?;
+    if factor == 1 {
+        return Ok(vec![1.0]);
+    }
+    fir::lowpass(TAPS_PER_PHASE * factor + 1, 1.0, 2 * factor, window::hamming)
+}
+
+
+/// Polyphase decimator.
+/// Output sample is computed for every M-th input sample starting with the first one.
+#[derive(Clone, Debug)]
+pub struct Decimator<T: Sample = f32> {
+    factor: usize,
+    // Branch p filters input samples x[mM - p]
+    branches: Vec<FirFilter<T>>,
+    // Phase of the next input sample
+    phase: usize,
+    // Sum of the branch outputs for the next output sample
+    acc: T,
+}
+
+impl<T: Sample> Decimator<T> {
+    /// Create decimator with the default anti-alias filter
+    ///   * factor - Decimation factor M
+    pub fn new(factor: usize) -> Result<Decimator<T>> {
+        let taps: Vec<T::Real> = to_real(&default_taps(factor)?);
+        Decimator::with_taps(factor, &taps)
+    }
+
+    /// Create decimator with the given anti-alias filter
+    pub fn with_taps(factor: usize, taps: &[T::Real]) -> Result<Decimator<T>> {
+        check_factor(factor)?;
+        let branches = polyphase(taps, factor, T::Real::one())?;
+        Ok(Decimator { factor, branches, phase: 0, acc: T::default() })
+    }
+
+    /// Decimation factor
+    pub fn factor(&self) -> usize {
+        self.factor
+    }
+
+    /// Clear filter state
+    pub fn reset(&mut self) {
+        self.branches.iter_mut().for_each(|b| b.reset());
+        self.phase = 0;
+        self.acc = T::default();
+    }
+}
+
+impl<T: Sample> ProcessNode<T, T> for Decimator<T> {
+    fn process_buffer(&mut self, input_buffer: &[T], output_buffer: &mut [T]) -> Result<usize> {
+        check_output(output_buffer.len(), self.output_size(input_buffer.len()))?;
+        let mut n = 0;
+        for x in input_buffer {
+            let branch = (self.factor - self.phase) % self.factor;
+            self.acc = self.acc + self.branches[branch].process_sample(*x);
+            if branch == 0 {
+                output_buffer[n] = self.acc;
+                self.acc = T::default();
+                n += 1;
+            }
+            self.phase = (self.phase + 1) % self.factor;
+        }
+        Ok(n)
+    }
+
+    fn output_size(&self, input_size: usize) -> usize {
+        // Samples with phase 0 are kept
+        let first = (self.factor - self.phase) % self.factor;
+        input_size.saturating_sub(first).div_ceil(self.factor)
+    }
+}
+
+
+/// Polyphase interpolator.
+/// Each input sample produces L output samples.
+#[derive(Clone, Debug)]
+pub struct Interpolator<T: Sample = f32> {
+    factor: usize,
+    // Branch p computes output samples y[nL + p]
+    branches: Vec<FirFilter<T>>,
+}
+
+impl<T: Sample> Interpolator<T> {
+    /// Create interpolator with the default anti-image filter
+    ///   * factor - Interpolation factor L
+    pub fn new(factor: usize) -> Result<Interpolator<T>> {
+        let taps: Vec<T::Real> = to_real(&default_taps(factor)?);
+        Interpolator::with_taps(factor, &taps)
+    }
+
+    /// Create interpolator with the given anti-image filter.
+    /// Taps are scaled by L to keep the signal amplitude.
+    pub fn with_taps(factor: usize, taps: &[T::Real]) -> Result<Interpolator<T>> {
+        check_factor(factor)?;
+        let branches = polyphase(taps, factor, cast(factor).unwrap())?;
+        Ok(Interpolator { factor, branches })
+    }
+
+    /// Interpolation factor
+    pub fn factor(&self) -> usize {
+        self.factor
+    }
+
+    /// Clear filter state
+    pub fn reset(&mut self) {
+        self.branches.iter_mut().for_each(|b| b.reset());
+    }
+}
+
+impl<T: Sample> ProcessNode<T, T> for Interpolator<T> {
+    fn process_buffer(&mut self, input_buffer: &[T], output_buffer: &mut [T]) -> Result<usize> {
+        check_output(output_buffer.len(), self.output_size(input_buffer.len()))?;
+        for (x, output) in input_buffer.iter().zip(output_buffer.chunks_mut(self.factor)) {
+            for (y, branch) in output.iter_mut().zip(self.branches.iter_mut()) {
+                *y = branch.process_sample(*x);
+            }
+        }
+        Ok(input_buffer.len() * self.factor)
+    }
+
+    fn output_size(&self, input_size: usize) -> usize {
+        input_size * self.factor
+    }
+}
+
+
+/// Rational resampler: output rate = input rate * L / M.
+/// The factors are reduced by their greatest common divisor.
+///
+/// Example
+///
+/// ```
+/// use dsp::runtime::node::ProcessNode;
+/// use dsp::filter::multirate::RationalResampler;
+///
+/// // 44.1kHz to 16kHz
+/// let mut resampler: RationalResampler = RationalResampler::from_rates(44_100, 16_000).unwrap();
+/// assert_eq!(resampler.factors(), (160, 441));
+/// let input = vec![0.0; 441];
+/// let mut output = vec![0.0; resampler.output_size(441)];
+/// assert_eq!(resampler.process_buffer(&input, &mut output).unwrap(), 160);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RationalResampler<T: Sample = f32> {
+    interpolation: usize,
+    decimation: usize,
+    bank: FilterBank<T>,
+    // Branch of the next output sample. Values >= L belong to the next input samples
+    phase: usize,
+}
+
+impl<T: Sample> RationalResampler<T> {
+    /// Create resampler with the default lowpass filter
+    ///   * interpolation - Interpolation factor L
+    ///   * decimation - Decimation factor M
+    pub fn new(interpolation: usize, decimation: usize) -> Result<RationalResampler<T>> {
+        check_factor(interpolation)?;
+        check_factor(decimation)?;
+        let d = gcd(interpolation, decimation);
+        let (l, m) = (interpolation / d, decimation / d);
+        let taps: Vec<T::Real> = to_real(&default_taps(usize::max(l, m))?);
+        RationalResampler::with_taps(l, m, &taps)
+    }
+
+    /// Create resampler which converts between the given sample rates
+    pub fn from_rates(input_rate: usize, output_rate: usize) -> Result<RationalResampler<T>> {
+        RationalResampler::new(output_rate, input_rate)
+    }
+
+    /// Create resampler with the given lowpass filter designed for the rate L * input rate.
+    /// Taps are scaled by L to keep the signal amplitude.
+    pub fn with_taps(interpolation: usize, decimation: usize, taps: &[T::Real]) -> Result<RationalResampler<T>> {
+        check_factor(interpolation)?;
+        check_factor(decimation)?;
+        let d = gcd(interpolation, decimation);
+        let (l, m) = (interpolation / d, decimation / d);
+        let bank = FilterBank::new(taps, l, l, taps.len().div_ceil(l), cast(l).unwrap())?;
+        Ok(RationalResampler { interpolation: l, decimation: m, bank, phase: 0 })
+    }
+
+    /// Reduced interpolation and decimation factors (L, M)
+    pub fn factors(&self) -> (usize, usize) {
+        (self.interpolation, self.decimation)
+    }
+
+    /// Clear filter state
+    pub fn reset(&mut self) {
+        self.bank.reset();
+        self.phase = 0;
+    }
+}
+
+impl<T: Sample> ProcessNode<T, T> for RationalResampler<T> {
+    fn process_buffer(&mut self, input_buffer: &[T], output_buffer: &mut [T]) -> Result<usize> {
+        check_output(output_buffer.len(), self.output_size(input_buffer.len()))?;
+        let mut n = 0;
+        for x in input_buffer {
+            self.bank.push(*x);
+            while self.phase < self.interpolation {
+                output_buffer[n] = self.bank.output(self.phase);
+                n += 1;
+                self.phase += self.decimation;
+            }
+            self.phase -= self.interpolation;
+        }
+        Ok(n)
+    }
+
+    fn output_size(&self, input_size: usize) -> usize {
+        // Output samples at high rate positions phase, phase + M, ... before L * input_size
+        (input_size * self.interpolation).saturating_sub(self.phase).div_ceil(self.decimation)
+    }
+}
+
+
+/// Arbitrary ratio resampler.
+/// Output samples are linearly interpolated between the neighbouring branches
+/// of the polyphase filter bank.
+///
+/// Anti-alias filter is designed for the initial ratio. `set_ratio` is meant for small changes.
+/// For large downsampling ratios use `Decimator` first, since the filter length grows with 1/ratio.
+///
+/// Example
+///
+/// ```
+/// use dsp::runtime::node::ProcessNode;
+/// use dsp::filter::multirate::Resampler;
+///
+/// let mut resampler: Resampler = Resampler::new(48_000.0 / 44_100.0).unwrap();
+/// let input = vec![1.0; 441];
+/// let mut output = vec![0.0; resampler.output_size(441)];
+/// let n = resampler.process_buffer(&input, &mut output).unwrap();
+/// // 480 samples, the last one can be added by the rounding error
+/// assert!(n == 480 || n == 481);
+///
+/// // Sender clock is a bit faster
+/// resampler.set_ratio(48_000.0 / 44_101.0).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Resampler<T: Sample = f32> {
+    ratio: f64,
+    // Input samples per output sample
+    step: f64,
+    bank: FilterBank<T>,
+    // Position of the next output sample after the last input sample, in input samples
+    offset: f64,
+}
+
+impl<T: Sample> Resampler<T> {
+    /// Create resampler with the default lowpass filter
+    ///   * ratio - output rate / input rate
+    pub fn new(ratio: f64) -> Result<Resampler<T>> {
+        check_ratio(ratio)?;
+        let cutoff = ratio.min(1.0);
+        let taps_per_phase = (TAPS_PER_PHASE as f64 / cutoff).ceil() as usize;
+        let num_taps = NUM_PHASES * taps_per_phase + 1;
+        let taps = fir::lowpass(num_taps, cutoff as f32, 2 * NUM_PHASES, window::hamming)?;
+        Resampler::with_taps(ratio, &to_real(&taps))
+    }
+
+    /// Create resampler with the given lowpass filter designed for the rate 64 * input rate.
+    pub fn with_taps(ratio: f64, taps: &[T::Real]) -> Result<Resampler<T>> {
+        check_ratio(ratio)?;
+        // Extra branch is the first branch delayed by one sample, used by the interpolation
+        let branch_len = taps.len().saturating_sub(1).div_ceil(NUM_PHASES).max(1);
+        let bank = FilterBank::new(taps, NUM_PHASES, NUM_PHASES + 1, branch_len, cast(NUM_PHASES).unwrap())?;
+        Ok(Resampler { ratio, step: 1.0 / ratio, bank, offset: 0.0 })
+    }
+
+    /// Ratio output rate / input rate
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    /// Change resampling ratio without resetting the state
+    pub fn set_ratio(&mut self, ratio: f64) -> Result<()> {
+        check_ratio(ratio)?;
+        self.ratio = ratio;
+        self.step = 1.0 / ratio;
+        Ok(())
+    }
+
+    /// Clear filter state
+    pub fn reset(&mut self) {
+        self.bank.reset();
+        self.offset = 0.0;
+    }
+}
+
+impl<T: Sample> ProcessNode<T, T> for Resampler<T> {
+    fn process_buffer(&mut self, input_buffer: &[T], output_buffer: &mut [T]) -> Result<usize> {
+        check_output(output_buffer.len(), self.output_size(input_buffer.len()))?;
+        let mut n = 0;
+        for x in input_buffer {
+            self.bank.push(*x);
+            while self.offset < 1.0 {
+                let pos = self.offset * NUM_PHASES as f64;
+                let branch = pos.floor() as usize;
+                let frac: T::Real = cast(pos - branch as f64).unwrap();
+                let y0 = self.bank.output(branch);
+                let y1 = self.bank.output(branch + 1);
+                output_buffer[n] = y0 + (y1 - y0) * frac;
+                n += 1;
+                self.offset += self.step;
+            }
+            self.offset -= 1.0;
+        }
+        Ok(n)
+    }
+
+    fn output_size(&self, input_size: usize) -> usize {
+        // One extra sample for the rounding errors
+        let n = (input_size as f64 - self.offset).max(0.0) / self.step;
+        n.ceil() as usize + 1
+    }
+}
+
+
+/// Polyphase filter bank with the shared delay line
+#[derive(Clone, Debug)]
+struct FilterBank<T: Sample> {
+    // Taps of each branch in reversed order
+    branches: Vec<Vec<T::Real>>,
+    // Delay line stored twice, so the last samples are always in one continuous slice
+    history: Vec<T>,
+    pos: usize,
+}
+
+impl<T: Sample> FilterBank<T> {
+    /// Branch p gets taps h[jN + p] for j < branch_len multiplied by the gain.
+    /// Missing taps are zeros.
+    ///   * stride - Number of phases N
+    fn new(taps: &[T::Real], stride: usize, num_branches: usize, branch_len: usize, gain: T::Real) -> Result<FilterBank<T>> {
+        if taps.is_empty() {
+            return Err(Error::Config("Filter requires at least one tap".to_string()));
+        }
+        let branches = (0..num_branches)
+            .map(|p| (0..branch_len).rev()
+                .map(|j| taps.get(j * stride + p).map_or(T::Real::default(), |t| *t * gain))
+                .collect())
+            .collect();
+        Ok(FilterBank { branches, history: vec![T::default(); 2 * branch_len], pos: 0 })
+    }
+
+    fn push(&mut self, x: T) {
+        let n = self.history.len() / 2;
+        self.pos = (self.pos + 1) % n;
+        self.history[self.pos] = x;
+        self.history[self.pos + n] = x;
+    }
+
+    /// Output of the given branch for the last input sample
+    fn output(&self, branch: usize) -> T {
+        let n = self.history.len() / 2;
+        let window = &self.history[self.pos + 1..self.pos + 1 + n];
+        window.iter().zip(self.branches[branch].iter())
+            .fold(T::default(), |acc, (x, h)| acc + *x * *h)
+    }
+
+    fn reset(&mut self) {
+        self.history.fill(T::default());
+        self.pos = 0;
+    }
+}
+
+
+/// Split taps into branches: branch p gets taps h[jM + p] multiplied by the gain
+fn polyphase<T: Sample>(taps: &[T::Real], factor: usize, gain: T::Real) -> Result<Vec<FirFilter<T>>> {
+    if taps.is_empty() {
+        return Err(Error::Config("Filter requires at least one tap".to_string()));
+    }
+    let branches = (0..factor)
+        .map(|p| {
+            let branch: Vec<T::Real> = taps.iter().skip(p).step_by(factor).map(|t| *t * gain).collect();
+            // Branches without taps output zeros
+            if branch.is_empty() { FirFilter::new(&[T::Real::default()]) } else { FirFilter::new(&branch) }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(branches)
+}
+
+fn to_real<R: Float>(taps: &[f32]) -> Vec<R> {
+    taps.iter().map(|t| cast(*t).unwrap()).collect()
+}
+
+fn check_factor(factor: usize) -> Result<()> {
+    if factor == 0 {
+        Err(Error::Config("Rate change factor must be greater then 0".to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+fn check_ratio(ratio: f64) -> Result<()> {
+    if ratio > 0.0 && ratio.is_finite() {
+        Ok(())
+    } else {
+        Err(Error::Config(format!("Resampling ratio {} must be positive", ratio)))
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+fn check_output(buffer_size: usize, required: usize) -> Result<()> {
+    if buffer_size < required {
+        Err(Error::Config(format!("Output buffer has size {} but {} samples are required", buffer_size, required)))
+    } else {
+        Ok(())
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+    use assert_approx_eq::assert_approx_eq;
+    use num_complex::Complex32;
+    use super::*;
+
+    /// Process signal in buffers of the given size
+    fn process<N: ProcessNode<T, T>, T: Sample>(node: &mut N, input: &[T], size: usize) -> Vec<T> {
+        let mut output = vec![];
+        for chunk in input.chunks(size) {
+            let mut buffer = vec![T::default(); node.output_size(chunk.len())];
+            let n = node.process_buffer(chunk, &mut buffer).unwrap();
+            output.extend_from_slice(&buffer[..n]);
+        }
+        output
+    }
+
+    #[test]
+    fn test_decimator_matches_fir() {
+        let taps = [0.1, 0.2, 0.4, 0.2, 0.1, -0.05, 0.3];
+        let input: Vec<f32> = (0..50).map(|i| ((i * 7 % 11) as f32) - 5.0).collect();
+        let mut fir: FirFilter = FirFilter::new(&taps).unwrap();
+        let filtered: Vec<f32> = input.iter().map(|x| fir.process_sample(*x)).collect();
+        let expected: Vec<f32> = filtered.iter().step_by(3).cloned().collect();
+        for size in [1, 2, 3, 7, 50] {
+            let mut decimator: Decimator = Decimator::with_taps(3, &taps).unwrap();
+            let output = process(&mut decimator, &input, size);
+            assert_eq!(output.len(), expected.len());
+            for (y, e) in output.iter().zip(&expected) {
+                assert_approx_eq!(y, e, 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_interpolator_matches_fir() {
+        let taps = [0.1, 0.2, 0.4, 0.2, 0.1, -0.05, 0.3];
+        let input: Vec<f32> = (0..20).map(|i| ((i * 7 % 11) as f32) - 5.0).collect();
+        let mut fir: FirFilter = FirFilter::new(&taps).unwrap();
+        let expected: Vec<f32> = input.iter()
+            .flat_map(|x| [*x, 0.0, 0.0, 0.0])
+            .map(|x| 4.0 * fir.process_sample(x))
+            .collect();
+        for size in [1, 3, 20] {
+            let mut interpolator: Interpolator = Interpolator::with_taps(4, &taps).unwrap();
+            let output = process(&mut interpolator, &input, size);
+            assert_eq!(output.len(), expected.len());
+            for (y, e) in output.iter().zip(&expected) {
+                assert_approx_eq!(y, e, 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_anti_alias() {
+        // Tone above the new Nyquist frequency is removed, tone below passes
+        let tone = |freq: f32| -> Vec<f32> { (0..4_000).map(|i| (2.0 * PI * freq * i as f32 / 8_000.0).sin()).collect() };
+        let mut decimator: Decimator = Decimator::new(4).unwrap();
+        let output = process(&mut decimator, &tone(1_500.0), 333);
+        let peak = output[200..].iter().fold(0.0f32, |acc, y| acc.max(y.abs()));
+        assert!(peak < 0.01);
+        let mut decimator: Decimator = Decimator::new(4).unwrap();
+        let output = process(&mut decimator, &tone(200.0), 333);
+        let rms = (output[200..].iter().map(|y| y * y).sum::<f32>() / 800.0).sqrt();
+        assert_approx_eq!(rms, 0.5f32.sqrt(), 0.01);
+    }
+
+    #[test]
+    fn test_complex_interpolation() {
+        // Complex tone keeps the amplitude after interpolation
+        let input: Vec<Complex32> = (0..200).map(|i| Complex32::from_polar(1.0, 0.3 * i as f32)).collect();
+        let mut interpolator: Interpolator<Complex32> = Interpolator::new(3).unwrap();
+        let output = process(&mut interpolator, &input, 17);
+        assert_eq!(output.len(), 600);
+        for y in &output[200..] {
+            assert_approx_eq!(y.norm(), 1.0, 0.01);
+        }
+    }
+
+    #[test]
+    fn test_rational_matches_interpolator() {
+        let taps = [0.1, 0.2, 0.4, 0.2, 0.1, -0.05, 0.3];
+        let input: Vec<f32> = (0..40).map(|i| ((i * 7 % 11) as f32) - 5.0).collect();
+        let mut interpolator: Interpolator = Interpolator::with_taps(3, &taps).unwrap();
+        let mut decimator: Decimator = Decimator::with_taps(2, &[1.0]).unwrap();
+        let expected = process(&mut decimator, &process(&mut interpolator, &input, 40), 120);
+        for size in [1, 5, 40] {
+            let mut resampler: RationalResampler = RationalResampler::with_taps(6, 4, &taps).unwrap();
+            assert_eq!(resampler.factors(), (3, 2));
+            let output = process(&mut resampler, &input, size);
+            assert_eq!(output.len(), 60);
+            for (y, e) in output.iter().zip(&expected) {
+                assert_approx_eq!(y, e, 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rational_44k_to_16k() {
+        let input: Vec<f32> = (0..44_100).map(|i| (2.0 * PI * 1_000.0 * i as f32 / 44_100.0).sin()).collect();
+        let mut resampler: RationalResampler = RationalResampler::from_rates(44_100, 16_000).unwrap();
+        let output = process(&mut resampler, &input, 1_024);
+        assert_eq!(output.len(), 16_000);
+        let rms = (output[1_000..].iter().map(|y| y * y).sum::<f32>() / 15_000.0).sqrt();
+        assert_approx_eq!(rms, 0.5f32.sqrt(), 0.01);
+    }
+
+    #[test]
+    fn test_arbitrary_ratio() {
+        // Output follows the input sine delayed by the filter
+        let freq = 0.02;
+        let input: Vec<f64> = (0..2_000).map(|i| (2.0 * std::f64::consts::PI * freq * i as f64).sin()).collect();
+        for ratio in [0.7, 1.0, 2.5] {
+            let mut resampler: Resampler<f64> = Resampler::new(ratio).unwrap();
+            let delay = (resampler.bank.branches[0].len() * NUM_PHASES) as f64 / (2.0 * NUM_PHASES as f64);
+            let output = process(&mut resampler, &input, 100);
+            assert!((output.len() as f64 - 2_000.0 * ratio).abs() <= 1.0);
+            let n = output.len();
+            for (k, y) in output.iter().enumerate().take(n * 3 / 4).skip(n / 4) {
+                let t = k as f64 / ratio - delay;
+                assert_approx_eq!(*y, (2.0 * std::f64::consts::PI * freq * t).sin(), 0.01);
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_ratio() {
+        let mut resampler: Resampler = Resampler::new(1.0).unwrap();
+        let mut output = vec![0.0; resampler.output_size(100)];
+        assert_eq!(resampler.process_buffer(&[1.0; 100], &mut output).unwrap(), 100);
+        resampler.set_ratio(1.01).unwrap();
+        assert_eq!(resampler.ratio(), 1.01);
+        let mut output = vec![0.0; resampler.output_size(1_000)];
+        // Position of the last output is 1000 with the rounding error
+        let n = resampler.process_buffer(&[1.0; 1_000], &mut output).unwrap();
+        assert!((1_010..=1_011).contains(&n));
+        assert!(resampler.set_ratio(0.0).is_err());
+        assert_approx_eq!(output[1_000], 1.0, 0.01);
+    }
+
+    #[test]
+    fn test_buffer_size() {
+        let mut decimator: Decimator = Decimator::new(3).unwrap();
+        assert_eq!(decimator.output_size(4), 2);
+        let mut output = [0.0; 2];
+        assert_eq!(decimator.process_buffer(&[1.0; 4], &mut output).unwrap(), 2);
+        // Next sample with phase 0 is the 3rd one
+        assert_eq!(decimator.output_size(2), 0);
+        assert_eq!(decimator.output_size(3), 1);
+        let mut interpolator: Interpolator = Interpolator::new(3).unwrap();
+        assert!(interpolator.process_buffer(&[1.0; 4], &mut [0.0; 11]).is_err());
+        assert!(Decimator::<f32>::new(0).is_err());
+    }
+}
@@ -0,0 +1,376 @@
+//! Cascade of second order sections
+//!
+//! High order IIR filter is numerically unstable when implemented directly from the
+//! transfer function. Splitting it into the second order sections keeps the poles
+//! of each section accurate.
+//!
+//! Each section is stored as [b0, b1, b2, a0, a1, a2] (the same layout as in SciPy)
+//! and processed in the transposed direct form II.
+
+use num_complex::Complex64;
+use num_traits::{cast, Float, One, Zero};
+use crate::{Error, Result};
+use crate::filter::biquad::{self, BiquadFilter};
+use crate::filter::design::iir::Zpk;
+use crate::filter::design::poly;
+use crate::runtime::node::ProcessNode;
+use crate::sample::Sample;
+
+
+/// Second order sections filter for real or complex samples
+///
+/// Example
+///
+/// ```
+/// use dsp::runtime::node::ProcessNode;
+/// use dsp::filter::design::iir::{self, Band, Prototype};
+/// use dsp::filter::sos::SosFilter;
+///
+/// let zpk = iir::digital(Prototype::Butterworth, 6, Band::Lowpass(1_000.0), 8_000).unwrap();
+/// let mut filter: SosFilter = SosFilter::from_zpk(&zpk).unwrap();
+/// assert_eq!(filter.sections().len(), 3);
+///
+/// let input = vec![1.0; 256];
+/// let mut output = vec![0.0; 256];
+/// filter.process_buffer(&input, &mut output).unwrap();
+/// assert!((output[255] - 1.0).abs() < 1e-3);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SosFilter<T: Sample = f32> {
+    // Normalized coefficients [b0, b1, b2, a1, a2] (a0 = 1)
+    sections: Vec<[T::Real; 5]>,
+    // Transposed direct form II state for each section
+    state: Vec<[T; 2]>,
+    gain: T::Real,
+}
+
+impl<T: Sample> SosFilter<T> {
+    /// Create filter from the sections [b0, b1, b2, a0, a1, a2] and overall gain
+    pub fn new(sections: &[[T::Real; 6]], gain: T::Real) -> Result<SosFilter<T>> {
+        if sections.is_empty() {
+            return Err(Error::Config("Filter requires at least one section".to_string()));
+        }
+        let sections = sections.iter()
+            .map(|s| {
+                if s[3].is_zero() {
+                    return Err(Error::Config("Coefficient a0 can't be 0".to_string()));
+                }
+                Ok([s[0] / s[3], s[1] / s[3], s[2] / s[3], s[4] / s[3], s[5] / s[3]])
+            })
+            .collect::<Result<Vec<[T::Real; 5]>>>()?;
+        let state = vec![[T::default(); 2]; sections.len()];
+        Ok(SosFilter { sections, state, gain })
+    }
+
+    /// Create filter from the sections and overall gain. 
+    /// Returns error if any section is unstable (has poles outside of the unit circle).
+    pub fn new_stable(sections: &[[T::Real; 6]], gain: T::Real) -> Result<SosFilter<T>> {
+        let filter = SosFilter::new(sections, gain)?;
+        biquad::check_stable(&filter)?;
+        Ok(filter)
+    }
+
+    /// Create filter from the cascade of biquads
+    pub fn from_biquads(biquads: &[BiquadFilter<T>]) -> Result<SosFilter<T>> {
+        let sections: Vec<[T::Real; 6]> = biquads.iter()
+            .map(|f| {
+                let (b, a) = f.coefficients();
+                [b[0], b[1], b[2], a[0], a[1], a[2]]
+            })
+            .collect();
+        SosFilter::new(&sections, T::Real::one())
+    }
+
+    /// Create filter from the digital zero-pole-gain representation
+    pub fn from_zpk(zpk: &Zpk) -> Result<SosFilter<T>> {
+        let unit = Zpk { zeros: zpk.zeros.clone(), poles: zpk.poles.clone(), gain: 1.0 };
+        let sections: Vec<[T::Real; 6]> = unit.sections().iter()
+            .map(|(b, a)| [real(b[0]), real(b[1]), real(b[2]), real(a[0]), real(a[1]), real(a[2])])
+            .collect();
+        SosFilter::new(&sections, real(zpk.gain))
+    }
+
+    /// Create filter from the transfer function coefficients (b, a) in powers of z^-1.
+    /// Leading zeros of b are kept as a delay. Returns error if a[0] is 0.
+    pub fn from_tf(b: &[f64], a: &[f64]) -> Result<SosFilter<T>> {
+        if a.first().map_or(true, |a0| *a0 == 0.0) {
+            return Err(Error::Config("Coefficient a0 can't be 0".to_string()));
+        }
+        let delay = b.iter().position(|x| *x != 0.0)
+            .ok_or_else(|| Error::Config("Transfer function coefficients can't be all 0".to_string()))?;
+        let b = trim_zeros(&b[delay..]);
+        let a = trim_zeros(a);
+        // Multiply numerator and denominator by z^(n-1), to get polynomials in z.
+        // Shorter polynomial gets the roots at the origin.
+        let n = usize::max(b.len(), a.len());
+        let with_origin = |xs: &[f64]| {
+            let mut roots = poly::roots(xs);
+            roots.resize(n - 1, Complex64::new(0.0, 0.0));
+            roots
+        };
+        let zpk = Zpk { zeros: with_origin(b), poles: with_origin(a), gain: b[0] / a[0] };
+        let mut filter = SosFilter::from_zpk(&zpk)?;
+        filter.add_delay(delay);
+        Ok(filter)
+    }
+
+    /// Multiply transfer function by z^-delay. Delay is moved into the sections
+    /// with zeros at the origin, and the new sections are added for the rest.
+    fn add_delay(&mut self, delay: usize) {
+        let (zero, one) = (T::Real::zero(), T::Real::one());
+        let mut delay = delay;
+        for s in self.sections.iter_mut() {
+            while delay > 0 && s[2].is_zero() && !(s[0].is_zero() && s[1].is_zero()) {
+                *s = [zero, s[0], s[1], s[3], s[4]];
+                delay -= 1;
+            }
+        }
+        while delay > 0 {
+            let section = if delay >= 2 { [zero, zero, one, zero, zero] } else { [zero, one, zero, zero, zero] };
+            self.sections.push(section);
+            self.state.push([T::default(); 2]);
+            delay = delay.saturating_sub(2);
+        }
+    }
+
+    /// Transfer function coefficients (b, a) in powers of z^-1
+    pub fn to_tf(&self) -> (Vec<f64>, Vec<f64>) {
+        let mut b = vec![f64_of(self.gain)];
+        let mut a = vec![1.0];
+        for s in &self.sections {
+            b = poly::multiply(&b, &[f64_of(s[0]), f64_of(s[1]), f64_of(s[2])]);
+            a = poly::multiply(&a, &[1.0, f64_of(s[3]), f64_of(s[4])]);
+        }
+        (b, a)
+    }
+
+    /// Zero-pole-gain representation of the filter
+    pub fn to_zpk(&self) -> Zpk {
+        let mut zpk = Zpk { zeros: vec![], poles: vec![], gain: f64_of(self.gain) };
+        for s in &self.sections {
+            let b = [f64_of(s[0]), f64_of(s[1]), f64_of(s[2])];
+            zpk.gain *= b.iter().find(|v| **v != 0.0).cloned().unwrap_or(0.0);
+            zpk.zeros.extend(poly::roots(&b));
+            zpk.poles.extend(poly::roots(&[1.0, f64_of(s[3]), f64_of(s[4])]));
+        }
+        zpk
+    }
+
+    /// Sections [b0, b1, b2, a0, a1, a2] normalized to a0 = 1
+    pub fn sections(&self) -> Vec<[T::Real; 6]> {
+        self.sections.iter().map(|s| [s[0], s[1], s[2], T::Real::one(), s[3], s[4]]).collect()
+    }
+
+    /// Overall filter gain
+    pub fn gain(&self) -> T::Real {
+        self.gain
+    }
+
+    /// Set overall filter gain
+    pub fn set_gain(&mut self, gain: T::Real) {
+        self.gain = gain;
+    }
+
+    /// Scale the gain, so the filter has unit gain at the given frequency.
+    /// E.g. 0 for lowpass, sample_rate/2 for highpass filters.
+    pub fn normalize_gain(&mut self, freq: f32, sample_rate: usize) -> Result<()> {
+        let z = Complex64::from_polar(1.0, -2.0 * std::f64::consts::PI * freq as f64 / sample_rate as f64);
+        let response = self.sections.iter().fold(Complex64::new(f64_of(self.gain), 0.0), |acc, s| {
+            let num = f64_of(s[0]) + z * (f64_of(s[1]) + z * f64_of(s[2]));
+            let den = 1.0 + z * (f64_of(s[3]) + z * f64_of(s[4]));
+            acc * num / den
+        });
+        if response.norm() == 0.0 || !response.norm().is_finite() {
+            return Err(Error::Config(format!("Can't normalize gain at frequency {}", freq)));
+        }
+        self.gain = self.gain / real(response.norm());
+        Ok(())
+    }
+
+    /// Clear filter state
+    pub fn reset(&mut self) {
+        self.state.fill([T::default(); 2]);
+    }
+
+    /// Process single sample
+    pub fn process_sample(&mut self, x: T) -> T {
+        let mut y = x * self.gain;
+        for (s, z) in self.sections.iter().zip(self.state.iter_mut()) {
+            let x = y;
+            y = x * s[0] + z[0];
+            z[0] = x * s[1] - y * s[3] + z[1];
+            z[1] = x * s[2] - y * s[4];
+        }
+        y
+    }
+}
+
+impl<T: Sample> ProcessNode<T, T> for SosFilter<T> {
+    fn process_buffer(&mut self, input_buffer: &[T], output_buffer: &mut [T]) -> Result<usize> {
+        let n = usize::min(input_buffer.len(), output_buffer.len());
+        for i in 0..n {
+            output_buffer[i] = self.process_sample(input_buffer[i]);
+        }
+        Ok(n)
+    }
+}
+
+
+fn real<R: Float>(v: f64) -> R {
+    cast(v).unwrap()
+}
+
+fn f64_of<R: Float>(v: R) -> f64 {
+    v.to_f64().unwrap()
+}
+
+/// Remove trailing zeros
+fn trim_zeros(xs: &[f64]) -> &[f64] {
+    let end = xs.iter().rposition(|x| *x != 0.0).map_or(0, |i| i + 1);
+    &xs[..end]
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use crate::filter::design::iir::{self, Band, Prototype};
+    use super::*;
+
+    fn butterworth() -> Zpk {
+        iir::digital(Prototype::Butterworth, 4, Band::Lowpass(1_000.0), 8_000).unwrap()
+    }
+
+    fn impulse_response(filter: &mut SosFilter) -> Vec<f32> {
+        let mut input = vec![0.0; 64];
+        input[0] = 1.0;
+        let mut output = vec![0.0; 64];
+        filter.process_buffer(&input, &mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn test_same_as_biquads() {
+        let mut biquads = butterworth().to_biquads().unwrap();
+        let mut filter = SosFilter::from_biquads(&biquads).unwrap();
+        let input: Vec<f32> = (0..64).map(|i| (i as f32 * 0.9).sin()).collect();
+        let mut expected = input.clone();
+        for biquad in biquads.iter_mut() {
+            let tmp = expected.clone();
+            biquad.process_buffer(&tmp, &mut expected).unwrap();
+        }
+        let mut output = vec![0.0; 64];
+        // Process in 2 buffers to check the state
+        filter.process_buffer(&input[..30], &mut output[..30]).unwrap();
+        filter.process_buffer(&input[30..], &mut output[30..]).unwrap();
+        for i in 0..64 {
+            assert_approx_eq!(output[i], expected[i], 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_tf_conversion() {
+        let mut filter = SosFilter::from_zpk(&butterworth()).unwrap();
+        let (b, a) = filter.to_tf();
+        assert_eq!(b.len(), 5);
+        assert_eq!(a.len(), 5);
+        assert_approx_eq!(a[0], 1.0, 1e-9);
+        // scipy.signal.butter(4, 0.25)
+        let expected_b = [0.01020948, 0.04083792, 0.06125688, 0.04083792, 0.01020948];
+        let expected_a = [1.0, -1.96842779, 1.73586071, -0.72447083, 0.12038960];
+        for i in 0..5 {
+            assert_approx_eq!(b[i], expected_b[i], 1e-6);
+            assert_approx_eq!(a[i], expected_a[i], 1e-6);
+        }
+
+        // Multiple zero at -1 can be found only with the limited precision
+        let mut other = SosFilter::from_tf(&b, &a).unwrap();
+        let expected = impulse_response(&mut filter);
+        let output = impulse_response(&mut other);
+        for i in 0..64 {
+            assert_approx_eq!(output[i], expected[i], 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_zpk_conversion() {
+        let zpk = butterworth();
+        let filter: SosFilter = SosFilter::from_zpk(&zpk).unwrap();
+        let other = filter.to_zpk();
+        assert_eq!(other.poles.len(), 4);
+        assert_eq!(other.zeros.len(), 4);
+        assert_approx_eq!(other.gain, zpk.gain, 1e-6);
+        for p in zpk.poles.iter() {
+            assert!(other.poles.iter().any(|q| (p - q).norm() < 1e-4));
+        }
+    }
+
+    #[test]
+    fn test_complex_samples() {
+        let mut filter: SosFilter<Complex64> = SosFilter::from_zpk(&butterworth()).unwrap();
+        let mut real: SosFilter<f64> = SosFilter::from_zpk(&butterworth()).unwrap();
+        let input: Vec<Complex64> = (0..64).map(|i| Complex64::new(1.0, (i as f64 * 0.2).sin())).collect();
+        let mut output = vec![Complex64::new(0.0, 0.0); 64];
+        filter.process_buffer(&input, &mut output).unwrap();
+        let im: Vec<f64> = input.iter().map(|x| x.im).collect();
+        let mut im_out = vec![0.0; 64];
+        real.process_buffer(&im, &mut im_out).unwrap();
+        for i in 0..64 {
+            assert_approx_eq!(output[i].im, im_out[i], 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_normalize_gain() {
+        let mut filter: SosFilter = SosFilter::from_zpk(&butterworth()).unwrap();
+        filter.set_gain(3.0);
+        filter.normalize_gain(0.0, 8_000).unwrap();
+        let input = vec![1.0; 200];
+        let mut output = vec![0.0; 200];
+        filter.process_buffer(&input, &mut output).unwrap();
+        assert_approx_eq!(output[199], 1.0, 1e-4);
+    }
+
+    #[test]
+    fn test_invalid_sections() {
+        assert!(SosFilter::<f32>::new(&[], 1.0).is_err());
+        assert!(SosFilter::<f32>::new(&[[1.0, 0.0, 0.0, 0.0, 0.0, 0.0]], 1.0).is_err());
+        assert!(SosFilter::<f32>::from_tf(&[0.0], &[1.0]).is_err());
+        assert!(matches!(SosFilter::<f32>::from_tf(&[1.0], &[0.0, 1.0]), Err(Error::Config(_))));
+        assert!(SosFilter::<f32>::from_tf(&[1.0], &[]).is_err());
+        let unstable = [[1.0, 0.0, 0.0, 1.0, -0.5, 0.0], [1.0, 0.0, 0.0, 1.0, 0.0, 1.0]];
+        assert!(SosFilter::<f32>::new(&unstable, 1.0).is_ok());
+        assert!(matches!(SosFilter::<f32>::new_stable(&unstable, 1.0), Err(Error::Config(_))));
+        assert!(SosFilter::<f32>::new_stable(&unstable[..1], 1.0).is_ok());
+    }
+
+    #[test]
+    fn test_tf_delay() {
+        // H(z) = z^-1
+        let mut filter = SosFilter::from_tf(&[0.0, 1.0], &[1.0]).unwrap();
+        assert_eq!(impulse_response(&mut filter)[..3], [0.0, 1.0, 0.0]);
+
+        // H(z) = 0.5z^-1 / (1 - 0.5z^-1)
+        let mut filter = SosFilter::from_tf(&[0.0, 0.5], &[1.0, -0.5]).unwrap();
+        let output = impulse_response(&mut filter);
+        assert_eq!(output[0], 0.0);
+        for (i, y) in output.iter().enumerate().take(10).skip(1) {
+            assert_approx_eq!(*y, 0.5f32.powi(i as i32), 1e-6);
+        }
+
+        // Round trip with the delay longer than the sections can absorb
+        let b = [0.0, 0.0, 0.0, 0.2, 0.1, 0.0];
+        let a = [1.0, -0.3, 0.02];
+        let filter = SosFilter::<f32>::from_tf(&b, &a).unwrap();
+        let (b2, a2) = filter.to_tf();
+        for (i, v) in b2.iter().enumerate() {
+            assert_approx_eq!(*v, b.get(i).cloned().unwrap_or(0.0), 1e-6);
+        }
+        for (i, v) in a2.iter().enumerate() {
+            assert_approx_eq!(*v, a.get(i).cloned().unwrap_or(0.0), 1e-6);
+        }
+    }
+}
@@ -5,6 +5,8 @@
 
 pub mod core;
 pub mod filter;
+pub mod runtime;
+pub mod sample;
 pub mod signal;
 pub mod spectrum;
 pub mod window;
@@ -12,6 +14,7 @@ pub use num_complex;
 #[cfg(feature = "audio")]
 pub mod audio;
 
+mod error;
 mod vector;
 
-
+pub use error::{Error, Result};
@@ -0,0 +1,592 @@
+//! Flowgraph connects nodes and runs them
+//! 
+//! Nodes are added to the flowgraph together with the port of the upstream node.
+//! The flowgraph allocates buffers between the nodes and runs them in the order 
+//! in which they were added. Buffers of the nodes which change the sample rate
+//! are sized with `ProcessNode::output_size`.
+//! 
+//! The flowgraph can also be run with each node on its own thread. 
+//! In this mode nodes are connected with bounded ring buffers. 
+//! A slow node blocks the upstream nodes when the ring buffer is full.
+//! 
+//! Example
+//! 
+//! ```
+//! use dsp::runtime::flowgraph::Flowgraph;
+//! use dsp::core::generator::Sine;
+//! use dsp::core::complex::{RealToComplex, ComplexToReal};
+//! use dsp::core::fft::{ForwardFFT, WindowType};
+//! use dsp::core::file::FileSink;
+//! 
+//! let mut fg = Flowgraph::new(512);
+//! let signal = fg.add_source(Sine::new(64.0, 512));
+//! let complex = fg.add_process(signal, RealToComplex::new());
+//! let spectrum = fg.add_process(complex, ForwardFFT::new(512, WindowType::Hann));
+//! let norm = fg.add_process(spectrum, ComplexToReal::new());
+//! let path = std::env::temp_dir().join("flowgraph.dat");
+//! fg.add_sink(norm, FileSink::new(path.to_str().unwrap()).unwrap());
+//! fg.run_for(4).unwrap();
+//! ```
+
+use std::any::Any;
+use std::marker::PhantomData;
+use std::thread;
+use crate::{Error, Result};
+use crate::runtime::node::{SourceNode, ProcessNode, SinkNode};
+use crate::runtime::ring_buffer::{ring_buffer, Consumer, Producer};
+
+
+/// Output port of the node added to the flowgraph.
+/// The type of the port is the type of the samples produced by the node.
+pub struct Port<T> {
+    id: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T> Port<T> {
+    fn new(id: usize) -> Port<T> {
+        Port { id, _marker: PhantomData }
+    }
+}
+
+impl<T> Clone for Port<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Port<T> {}
+
+
+/// Output of the node. Only first `len` samples are valid.
+struct Buffer<T> {
+    data: Vec<T>,
+    len: usize,
+}
+
+/// Buffers with the output of each node. Sinks don't have output buffer.
+type Buffers = Vec<Option<Box<dyn Any + Send>>>;
+
+/// Type erased ring buffer ends
+type AnyProducer = Box<dyn Any + Send>;
+type AnyConsumer = Box<dyn Any + Send>;
+
+/// Creates ring buffer for the samples produced by the node
+type ChannelFactory = fn(usize) -> Result<(AnyProducer, AnyConsumer)>;
+
+fn new_channel<T: Copy + Default + Send + 'static>(capacity: usize) -> Result<(AnyProducer, AnyConsumer)> {
+    let (producer, consumer) = ring_buffer::<T>(capacity)?;
+    Ok((Box::new(producer), Box::new(consumer)))
+}
+
+/// Type erased node
+trait Block: Send {
+    /// Run the node once
+    fn work(&mut self, buffers: &mut Buffers) -> Result<()>;
+
+    /// Run the node on the ring buffers until the input or the source is exhausted
+    fn run(self: Box<Self>, input: Option<AnyConsumer>, outputs: Vec<AnyProducer>, 
+           block_size: usize, max_steps: Option<usize>) -> Result<()>;
+}
+
+struct SourceBlock<T, N> {
+    node: N,
+    id: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T, N> Block for SourceBlock<T, N> 
+where 
+    T: Copy + Default + 'static,
+    N: SourceNode<T> + Send,
+{
+    fn work(&mut self, buffers: &mut Buffers) -> Result<()> {
+        let output = buffer_mut::<T>(&mut buffers[self.id]);
+        output.len = self.node.write_buffer(&mut output.data)?;
+        Ok(())
+    }
+
+    fn run(mut self: Box<Self>, _input: Option<AnyConsumer>, outputs: Vec<AnyProducer>, 
+           block_size: usize, max_steps: Option<usize>) -> Result<()> {
+        let mut outputs = producers::<T>(outputs);
+        let mut buffer = vec![T::default(); block_size];
+        let mut steps = 0;
+        while max_steps.map_or(true, |max| steps < max) {
+            let n = self.node.write_buffer(&mut buffer)?;
+            write_outputs(&mut outputs, &buffer[..n])?;
+            steps += 1;
+        }
+        Ok(())
+    }
+}
+
+struct ProcessBlock<I, O, N> {
+    node: N,
+    id: usize,
+    input: usize,
+    _marker: PhantomData<fn(I) -> O>,
+}
+
+impl<I, O, N> Block for ProcessBlock<I, O, N> 
+where 
+    I: Copy + Default + 'static,
+    O: Copy + Default + 'static,
+    N: ProcessNode<I, O> + Send,
+{
+    fn work(&mut self, buffers: &mut Buffers) -> Result<()> {
+        // Input node is always added before this one
+        let (head, tail) = buffers.split_at_mut(self.id);
+        let input = buffer::<I>(&head[self.input]);
+        let output = buffer_mut::<O>(&mut tail[0]);
+        output.len = self.node.process_buffer(&input.data[..input.len], &mut output.data)?;
+        Ok(())
+    }
+
+    fn run(mut self: Box<Self>, input: Option<AnyConsumer>, outputs: Vec<AnyProducer>, 
+           block_size: usize, _max_steps: Option<usize>) -> Result<()> {
+        let mut input = consumer::<I>(input);
+        let mut outputs = producers::<O>(outputs);
+        let mut input_buffer = vec![I::default(); block_size];
+        let mut output_buffer = vec![O::default(); self.node.output_size(block_size)];
+        loop {
+            let n = input.read(&mut input_buffer)?;
+            let m = self.node.process_buffer(&input_buffer[..n], &mut output_buffer)?;
+            write_outputs(&mut outputs, &output_buffer[..m])?;
+        }
+    }
+}
+
+struct SinkBlock<T, N> {
+    node: N,
+    input: usize,
+    _marker: PhantomData<fn(T)>,
+}
+
+impl<T, N> Block for SinkBlock<T, N> 
+where 
+    T: Copy + Default + 'static,
+    N: SinkNode<T> + Send,
+{
+    fn work(&mut self, buffers: &mut Buffers) -> Result<()> {
+        let input = buffer::<T>(&buffers[self.input]);
+        self.node.read_buffer(&input.data[..input.len])
+    }
+
+    fn run(mut self: Box<Self>, input: Option<AnyConsumer>, _outputs: Vec<AnyProducer>, 
+           block_size: usize, _max_steps: Option<usize>) -> Result<()> {
+        let mut input = consumer::<T>(input);
+        let mut buffer = vec![T::default(); block_size];
+        loop {
+            let n = input.read(&mut buffer)?;
+            self.node.read_buffer(&buffer[..n])?;
+        }
+    }
+}
+
+fn buffer<T: 'static>(buffer: &Option<Box<dyn Any + Send>>) -> &Buffer<T> {
+    buffer.as_ref()
+        .and_then(|b| b.downcast_ref::<Buffer<T>>())
+        .expect("Port type doesn't match the buffer")
+}
+
+fn buffer_mut<T: 'static>(buffer: &mut Option<Box<dyn Any + Send>>) -> &mut Buffer<T> {
+    buffer.as_mut()
+        .and_then(|b| b.downcast_mut::<Buffer<T>>())
+        .expect("Port type doesn't match the buffer")
+}
+
+fn consumer<T: 'static>(input: Option<AnyConsumer>) -> Consumer<T> {
+    *input.and_then(|c| c.downcast::<Consumer<T>>().ok())
+        .expect("Port type doesn't match the ring buffer")
+}
+
+fn producers<T: 'static>(outputs: Vec<AnyProducer>) -> Vec<Producer<T>> {
+    outputs.into_iter()
+        .map(|p| *p.downcast::<Producer<T>>().expect("Port type doesn't match the ring buffer"))
+        .collect()
+}
+
+/// Send samples to every connected node. 
+/// Stops only when all downstream nodes are finished.
+fn write_outputs<T: Copy>(outputs: &mut Vec<Producer<T>>, buffer: &[T]) -> Result<()> {
+    outputs.retain_mut(|output| output.write_all(buffer).is_ok());
+    if outputs.is_empty() {
+        Err(Error::EndOfStream)
+    } else {
+        Ok(())
+    }
+}
+
+/// Convert the payload of the panicked node thread into the error
+fn panic_error(payload: Box<dyn Any + Send>) -> Error {
+    let msg = payload.downcast_ref::<&str>().map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_default();
+    Error::NodePanic(msg)
+}
+
+
+/// Graph of connected nodes
+pub struct Flowgraph {
+    block_size: usize,
+    blocks: Vec<Box<dyn Block>>,
+    inputs: Vec<Option<usize>>,
+    channels: Vec<Option<ChannelFactory>>,
+    buffers: Buffers,
+    // Size of the output buffer of each node
+    sizes: Vec<usize>,
+}
+
+impl Flowgraph {
+    /// Create empty flowgraph
+    ///   * block_size - Number of samples processed by each node in a single step
+    pub fn new(block_size: usize) -> Flowgraph {
+        Flowgraph { block_size, blocks: vec![], inputs: vec![], channels: vec![], buffers: vec![], sizes: vec![] }
+    }
+
+    /// Add source node. Returns port with the samples generated by this node.
+    pub fn add_source<T, N>(&mut self, node: N) -> Port<T> 
+    where 
+        T: Copy + Default + Send + 'static,
+        N: SourceNode<T> + Send + 'static,
+    {
+        let id = self.blocks.len();
+        self.blocks.push(Box::new(SourceBlock { node, id, _marker: PhantomData }));
+        self.inputs.push(None);
+        self.channels.push(Some(new_channel::<T>));
+        self.buffers.push(Some(Box::new(Buffer { data: vec![T::default(); self.block_size], len: 0 })));
+        self.sizes.push(self.block_size);
+        Port::new(id)
+    }
+
+    /// Add process node connected to the given port. 
+    /// Returns port with the samples produced by this node.
+    pub fn add_process<I, O, N>(&mut self, input: Port<I>, node: N) -> Port<O> 
+    where 
+        I: Copy + Default + 'static,
+        O: Copy + Default + Send + 'static,
+        N: ProcessNode<I, O> + Send + 'static,
+    {
+        let id = self.blocks.len();
+        let size = node.output_size(self.sizes[input.id]);
+        self.blocks.push(Box::new(ProcessBlock { node, id, input: input.id, _marker: PhantomData }));
+        self.inputs.push(Some(input.id));
+        self.channels.push(Some(new_channel::<O>));
+        self.buffers.push(Some(Box::new(Buffer { data: vec![O::default(); size], len: 0 })));
+        self.sizes.push(size);
+        Port::new(id)
+    }
+
+    /// Add sink node connected to the given port
+    pub fn add_sink<T, N>(&mut self, input: Port<T>, node: N) 
+    where 
+        T: Copy + Default + 'static,
+        N: SinkNode<T> + Send + 'static,
+    {
+        self.blocks.push(Box::new(SinkBlock { node, input: input.id, _marker: PhantomData }));
+        self.inputs.push(Some(input.id));
+        self.channels.push(None);
+        self.buffers.push(None);
+        self.sizes.push(0);
+    }
+
+    /// Run every node once
+    pub fn step(&mut self) -> Result<()> {
+        for block in self.blocks.iter_mut() {
+            block.work(&mut self.buffers)?;
+        }
+        Ok(())
+    }
+
+    /// Run the flowgraph given number of times or until a source is exhausted
+    pub fn run_for(&mut self, steps: usize) -> Result<()> {
+        for _ in 0..steps {
+            match self.step() {
+                Err(Error::EndOfStream) => return Ok(()),
+                result => result?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Run the flowgraph until a source is exhausted
+    pub fn run(&mut self) -> Result<()> {
+        loop {
+            match self.step() {
+                Err(Error::EndOfStream) => return Ok(()),
+                result => result?,
+            }
+        }
+    }
+
+    /// Run each node on its own thread until the sources are exhausted.
+    ///   * capacity - Number of samples which can be buffered between 2 nodes, greater then 0
+    pub fn run_threaded(self, capacity: usize) -> Result<()> {
+        self.spawn(capacity, None)
+    }
+
+    /// Run each node on its own thread. Sources stop after producing given number of blocks.
+    ///   * capacity - Number of samples which can be buffered between 2 nodes, greater then 0
+    pub fn run_threaded_for(self, capacity: usize, steps: usize) -> Result<()> {
+        self.spawn(capacity, Some(steps))
+    }
+
+    fn spawn(self, capacity: usize, max_steps: Option<usize>) -> Result<()> {
+        // Create one ring buffer for each connection
+        let mut inputs: Vec<Option<AnyConsumer>> = self.inputs.iter().map(|_| None).collect();
+        let mut outputs: Vec<Vec<AnyProducer>> = self.inputs.iter().map(|_| vec![]).collect();
+        for (id, input) in self.inputs.iter().enumerate() {
+            if let Some(src) = *input {
+                let new_channel = self.channels[src].expect("Sink can't be connected");
+                let (producer, consumer) = new_channel(capacity)?;
+                outputs[src].push(producer);
+                inputs[id] = Some(consumer);
+            }
+        }
+
+        let block_size = self.block_size;
+        let handles: Vec<_> = self.blocks.into_iter()
+            .zip(inputs.into_iter().zip(outputs))
+            .map(|(block, (input, outputs))| {
+                thread::spawn(move || block.run(input, outputs, block_size, max_steps))
+            })
+            .collect();
+
+        // Report first error other then end of stream
+        let mut result = Ok(());
+        for handle in handles {
+            match handle.join().unwrap_or_else(|err| Err(panic_error(err))) {
+                Ok(()) | Err(Error::EndOfStream) => (),
+                Err(err) => if result.is_ok() { result = Err(err) },
+            }
+        }
+        result
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use super::*;
+    use crate::core::generator::Step;
+    use crate::core::multiply::MultiplyConst;
+    use crate::filter::multirate::{Decimator, Interpolator};
+
+    struct VecSink {
+        data: Arc<Mutex<Vec<f32>>>,
+    }
+
+    impl SinkNode<f32> for VecSink {
+        fn read_buffer(&mut self, buffer: &[f32]) -> Result<()> {
+            self.data.lock().unwrap().extend_from_slice(buffer);
+            Ok(())
+        }
+    }
+
+    struct CountDown {
+        blocks: usize,
+    }
+
+    impl SourceNode<f32> for CountDown {
+        fn write_buffer(&mut self, buffer: &mut [f32]) -> Result<usize> {
+            if self.blocks == 0 {
+                return Err(Error::EndOfStream);
+            }
+            self.blocks -= 1;
+            buffer.iter_mut().for_each(|v| *v = self.blocks as f32);
+            Ok(buffer.len())
+        }
+    }
+
+    struct VecSource {
+        data: Vec<f32>,
+        pos: usize,
+    }
+
+    impl SourceNode<f32> for VecSource {
+        fn write_buffer(&mut self, buffer: &mut [f32]) -> Result<usize> {
+            let n = usize::min(buffer.len(), self.data.len() - self.pos);
+            if n == 0 {
+                return Err(Error::EndOfStream);
+            }
+            buffer[..n].copy_from_slice(&self.data[self.pos..self.pos+n]);
+            self.pos += n;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_partial_block() {
+        let data = Arc::new(Mutex::new(vec![]));
+        let mut fg = Flowgraph::new(2);
+        let src = fg.add_source(VecSource { data: vec![1., 2., 3., 4., 5.], pos: 0 });
+        let gain = fg.add_process(src, MultiplyConst::new(2.));
+        fg.add_sink(gain, VecSink { data: data.clone() });
+        fg.run().unwrap();
+
+        assert_eq!(*data.lock().unwrap(), vec![2., 4., 6., 8., 10.]);
+    }
+
+    #[test]
+    fn test_threaded_partial_block() {
+        let data = Arc::new(Mutex::new(vec![]));
+        let mut fg = Flowgraph::new(2);
+        let src = fg.add_source(VecSource { data: vec![1., 2., 3., 4., 5.], pos: 0 });
+        let gain = fg.add_process(src, MultiplyConst::new(2.));
+        fg.add_sink(gain, VecSink { data: data.clone() });
+        fg.run_threaded(3).unwrap();
+
+        assert_eq!(*data.lock().unwrap(), vec![2., 4., 6., 8., 10.]);
+    }
+
+    #[test]
+    fn test_rate_change() {
+        let data = Arc::new(Mutex::new(vec![]));
+        let mut fg = Flowgraph::new(2);
+        let src = fg.add_source(VecSource { data: vec![1., 2., 3., 4., 5.], pos: 0 });
+        let up = fg.add_process(src, Interpolator::with_taps(3, &[1. / 3.; 3]).unwrap());
+        let down = fg.add_process(up, Decimator::with_taps(2, &[1.]).unwrap());
+        fg.add_sink(down, VecSink { data: data.clone() });
+        fg.run().unwrap();
+
+        assert_eq!(*data.lock().unwrap(), vec![1., 1., 2., 3., 3., 4., 5., 5.]);
+    }
+
+    #[test]
+    fn test_run_for() {
+        let data = Arc::new(Mutex::new(vec![]));
+        let mut fg = Flowgraph::new(4);
+        let src = fg.add_source(Step::new(2));
+        let gain = fg.add_process(src, MultiplyConst::new(3.));
+        fg.add_sink(gain, VecSink { data: data.clone() });
+        fg.run_for(2).unwrap();
+
+        assert_eq!(*data.lock().unwrap(), vec![0., 0., 3., 3., 3., 3., 3., 3.]);
+    }
+
+    #[test]
+    fn test_fan_out() {
+        let data1 = Arc::new(Mutex::new(vec![]));
+        let data2 = Arc::new(Mutex::new(vec![]));
+        let mut fg = Flowgraph::new(2);
+        let src = fg.add_source(Step::new(0));
+        let gain1 = fg.add_process(src, MultiplyConst::new(2.));
+        let gain2 = fg.add_process(src, MultiplyConst::new(-1.));
+        fg.add_sink(gain1, VecSink { data: data1.clone() });
+        fg.add_sink(gain2, VecSink { data: data2.clone() });
+        fg.run_for(1).unwrap();
+
+        assert_eq!(*data1.lock().unwrap(), vec![2., 2.]);
+        assert_eq!(*data2.lock().unwrap(), vec![-1., -1.]);
+    }
+
+    #[test]
+    fn test_run_until_end_of_stream() {
+        let data = Arc::new(Mutex::new(vec![]));
+        let mut fg = Flowgraph::new(2);
+        let src = fg.add_source(CountDown { blocks: 3 });
+        fg.add_sink(src, VecSink { data: data.clone() });
+        fg.run().unwrap();
+
+        assert_eq!(*data.lock().unwrap(), vec![2., 2., 1., 1., 0., 0.]);
+    }
+
+    #[test]
+    fn test_threaded_until_end_of_stream() {
+        let data = Arc::new(Mutex::new(vec![]));
+        let mut fg = Flowgraph::new(2);
+        let src = fg.add_source(CountDown { blocks: 100 });
+        let gain = fg.add_process(src, MultiplyConst::new(2.));
+        fg.add_sink(gain, VecSink { data: data.clone() });
+        fg.run_threaded(3).unwrap();
+
+        let expected: Vec<f32> = (0..100).rev().flat_map(|i| vec![2. * i as f32; 2]).collect();
+        assert_eq!(*data.lock().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_threaded_run_for() {
+        let data1 = Arc::new(Mutex::new(vec![]));
+        let data2 = Arc::new(Mutex::new(vec![]));
+        let mut fg = Flowgraph::new(4);
+        let src = fg.add_source(Step::new(2));
+        let gain = fg.add_process(src, MultiplyConst::new(3.));
+        fg.add_sink(gain, VecSink { data: data1.clone() });
+        fg.add_sink(src, VecSink { data: data2.clone() });
+        fg.run_threaded_for(16, 2).unwrap();
+
+        assert_eq!(*data1.lock().unwrap(), vec![0., 0., 3., 3., 3., 3., 3., 3.]);
+        assert_eq!(*data2.lock().unwrap(), vec![0., 0., 1., 1., 1., 1., 1., 1.]);
+    }
+
+    #[test]
+    fn test_threaded_zero_capacity() {
+        let data = Arc::new(Mutex::new(vec![]));
+        let mut fg = Flowgraph::new(2);
+        let src = fg.add_source(Step::new(0));
+        fg.add_sink(src, VecSink { data });
+        assert!(matches!(fg.run_threaded(0), Err(Error::Config(_))));
+    }
+
+    struct PanicSink;
+
+    impl SinkNode<f32> for PanicSink {
+        fn read_buffer(&mut self, _buffer: &[f32]) -> Result<()> {
+            panic!("Sink failed");
+        }
+    }
+
+    #[test]
+    fn test_threaded_node_panic() {
+        let mut fg = Flowgraph::new(2);
+        let src = fg.add_source(Step::new(0));
+        fg.add_sink(src, PanicSink);
+        match fg.run_threaded(4) {
+            Err(Error::NodePanic(msg)) => assert_eq!(msg, "Sink failed"),
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    struct Counter {
+        produced: Arc<AtomicUsize>,
+    }
+
+    impl SourceNode<f32> for Counter {
+        fn write_buffer(&mut self, buffer: &mut [f32]) -> Result<usize> {
+            self.produced.fetch_add(1, Ordering::SeqCst);
+            Ok(buffer.len())
+        }
+    }
+
+    struct SlowSink {
+        produced: Arc<AtomicUsize>,
+        consumed: usize,
+    }
+
+    impl SinkNode<f32> for SlowSink {
+        fn read_buffer(&mut self, _buffer: &[f32]) -> Result<()> {
+            std::thread::sleep(Duration::from_millis(1));
+            self.consumed += 1;
+            // Source can be ahead by the blocks stored in the ring buffer and the block in progress
+            assert!(self.produced.load(Ordering::SeqCst) <= self.consumed + 4);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_threaded_backpressure() {
+        let produced = Arc::new(AtomicUsize::new(0));
+        let mut fg = Flowgraph::new(8);
+        let src = fg.add_source(Counter { produced: produced.clone() });
+        fg.add_sink(src, SlowSink { produced: produced.clone(), consumed: 0 });
+        fg.run_threaded_for(16, 50).unwrap();
+
+        assert_eq!(produced.load(Ordering::SeqCst), 50);
+    }
+}
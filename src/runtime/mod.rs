@@ -0,0 +1,7 @@
+//! # Runtime
+//!
+//! Traits implemented by all the nodes and the flowgraph which connects them
+//! 
+pub mod flowgraph;
+pub mod node;
+pub mod ring_buffer;
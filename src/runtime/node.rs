@@ -0,0 +1,84 @@
+//! Node traits
+//! 
+//! Every block in this crate is one of:
+//!   * SourceNode - generates samples (generators, file and network sources)
+//!   * ProcessNode - converts input samples into output samples (filters, FFT, converters)
+//!   * SinkNode - consumes samples (file and network sinks)
+//! 
+//! Sources and processors return the number of samples written to the output buffer.
+//! Sources which can be exhausted (like files) return EndOfStream error when there are no more samples.
+//! 
+//! Nodes can be boxed and used as trait objects.
+//! Example
+//! 
+//! ```
+//! use dsp::runtime::node::{SourceNode, ProcessNode};
+//! use dsp::core::generator::{Sine, Square};
+//! use dsp::core::multiply::MultiplyConst;
+//! 
+//! let mut sources: Vec<Box<dyn SourceNode<f32>>> = vec![
+//!     Box::new(Sine::new(2.0, 8)),
+//!     Box::new(Square::new(2.0, 8)),
+//! ];
+//! let mut gain: Box<dyn ProcessNode<f32, f32>> = Box::new(MultiplyConst::new(2.0));
+//! let mut buffer = vec![0.0; 8];
+//! let mut output = vec![0.0; 8];
+//! for source in sources.iter_mut() {
+//!     source.write_buffer(&mut buffer).unwrap();
+//!     gain.process_buffer(&buffer, &mut output).unwrap();
+//! }
+//! assert_eq!(output[0], 2.0);
+//! ```
+
+use crate::Result;
+
+
+/// Node which generates samples
+pub trait SourceNode<T> {
+    /// Fill the buffer with the next samples. 
+    /// Returns number of samples written, which can be less then the buffer size 
+    /// if the source is almost exhausted. Returns EndOfStream error if there are no more samples.
+    fn write_buffer(&mut self, buffer: &mut [T]) -> Result<usize>;
+}
+
+/// Node which converts input samples into output samples
+pub trait ProcessNode<I, O> {
+    /// Process input buffer and save the result into output buffer.
+    /// Returns number of samples written to the output buffer.
+    fn process_buffer(&mut self, input_buffer: &[I], output_buffer: &mut [O]) -> Result<usize>;
+
+    /// Size of the output buffer required to process the given number of input samples.
+    /// Nodes which change the sample rate return more or less samples then they get.
+    fn output_size(&self, input_size: usize) -> usize {
+        input_size
+    }
+}
+
+/// Node which consumes samples
+pub trait SinkNode<T> {
+    /// Consume all samples from the buffer
+    fn read_buffer(&mut self, buffer: &[T]) -> Result<()>;
+}
+
+
+impl<T, N: SourceNode<T> + ?Sized> SourceNode<T> for Box<N> {
+    fn write_buffer(&mut self, buffer: &mut [T]) -> Result<usize> {
+        (**self).write_buffer(buffer)
+    }
+}
+
+impl<I, O, N: ProcessNode<I, O> + ?Sized> ProcessNode<I, O> for Box<N> {
+    fn process_buffer(&mut self, input_buffer: &[I], output_buffer: &mut [O]) -> Result<usize> {
+        (**self).process_buffer(input_buffer, output_buffer)
+    }
+
+    fn output_size(&self, input_size: usize) -> usize {
+        (**self).output_size(input_size)
+    }
+}
+
+impl<T, N: SinkNode<T> + ?Sized> SinkNode<T> for Box<N> {
+    fn read_buffer(&mut self, buffer: &[T]) -> Result<()> {
+        (**self).read_buffer(buffer)
+    }
+}
@@ -0,0 +1,289 @@
+//! Bounded single-producer/single-consumer ring buffer
+//! 
+//! Lock-free queue used to pass samples between nodes running on separate threads.
+//! Producer blocks when the buffer is full, so a slow consumer throttles the producer
+//! instead of losing samples. Waiting threads sleep on a condition variable.
+//! 
+//! Example
+//! 
+//! ```
+//! use std::thread;
+//! use dsp::runtime::ring_buffer::ring_buffer;
+//! 
+//! let (mut producer, mut consumer) = ring_buffer::<f32>(4).unwrap();
+//! let handle = thread::spawn(move || {
+//!     producer.write_all(&[1., 2., 3., 4., 5., 6.]).unwrap();
+//! });
+//! let mut buffer = vec![0.0; 6];
+//! consumer.read_exact(&mut buffer).unwrap();
+//! handle.join().unwrap();
+//! 
+//! assert_eq!(buffer, vec![1., 2., 3., 4., 5., 6.]);
+//! ```
+
+use std::cell::UnsafeCell;
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use crate::{Error, Result};
+
+
+struct Shared<T> {
+    buffer: Vec<UnsafeCell<T>>,
+    // Position of the next sample to read
+    head: AtomicUsize,
+    // Position of the next sample to write
+    tail: AtomicUsize,
+    // Set when producer or consumer is dropped
+    closed: AtomicBool,
+    // Wakes up the thread waiting for samples or free space
+    lock: Mutex<()>,
+    cond: Condvar,
+}
+
+// Slots are accessed by at most one thread at a time. 
+// Ownership of the slot is passed between threads with the head and tail positions.
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+    /// Number of samples in the buffer
+    fn len(&self) -> usize {
+        self.tail.load(Ordering::Acquire).wrapping_sub(self.head.load(Ordering::Acquire))
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// Sleep until the condition is true or the other end is dropped
+    fn wait_until(&self, ready: impl Fn(&Self) -> bool) {
+        let mut guard = self.lock.lock().unwrap_or_else(|err| err.into_inner());
+        while !ready(self) && !self.is_closed() {
+            guard = self.cond.wait(guard).unwrap_or_else(|err| err.into_inner());
+        }
+    }
+
+    /// Wake up the waiting thread. Taking the lock ensures that the change of the positions
+    /// is visible to the thread which checks the condition in `wait_until`.
+    fn notify(&self) {
+        let _guard = self.lock.lock().unwrap_or_else(|err| err.into_inner());
+        self.cond.notify_all();
+    }
+}
+
+
+/// Create ring buffer which can hold `capacity` samples
+pub fn ring_buffer<T: Copy + Default>(capacity: usize) -> Result<(Producer<T>, Consumer<T>)> {
+    if capacity == 0 {
+        return Err(Error::Config("Ring buffer capacity must be greater then 0".to_string()));
+    }
+    let shared = Arc::new(Shared {
+        buffer: (0..capacity).map(|_| UnsafeCell::new(T::default())).collect(),
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+        closed: AtomicBool::new(false),
+        lock: Mutex::new(()),
+        cond: Condvar::new(),
+    });
+    Ok((Producer { shared: shared.clone() }, Consumer { shared }))
+}
+
+
+/// Writing end of the ring buffer
+pub struct Producer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T: Copy> Producer<T> {
+    /// Write as many samples as fits into the buffer without blocking.
+    /// Returns number of written samples.
+    pub fn push_slice(&mut self, data: &[T]) -> usize {
+        let capacity = self.shared.buffer.len();
+        let head = self.shared.head.load(Ordering::Acquire);
+        let tail = self.shared.tail.load(Ordering::Relaxed);
+        let n = usize::min(capacity - tail.wrapping_sub(head), data.len());
+        for (i, x) in data[..n].iter().enumerate() {
+            let slot = &self.shared.buffer[tail.wrapping_add(i) % capacity];
+            unsafe { *slot.get() = *x };
+        }
+        self.shared.tail.store(tail.wrapping_add(n), Ordering::Release);
+        if n > 0 {
+            self.shared.notify();
+        }
+        n
+    }
+
+    /// Write all samples, waiting for the consumer if the buffer is full.
+    /// Returns EndOfStream if the consumer was dropped.
+    pub fn write_all(&mut self, mut data: &[T]) -> Result<()> {
+        while !data.is_empty() {
+            if self.is_closed() {
+                return Err(Error::EndOfStream);
+            }
+            let n = self.push_slice(data);
+            if n == 0 {
+                self.shared.wait_until(|s| s.len() < s.buffer.len());
+            }
+            data = &data[n..];
+        }
+        Ok(())
+    }
+
+    /// True if the consumer was dropped
+    pub fn is_closed(&self) -> bool {
+        self.shared.is_closed()
+    }
+}
+
+impl<T> Drop for Producer<T> {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
+        self.shared.notify();
+    }
+}
+
+
+/// Reading end of the ring buffer
+pub struct Consumer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T: Copy> Consumer<T> {
+    /// Read available samples without blocking.
+    /// Returns number of read samples.
+    pub fn pop_slice(&mut self, buffer: &mut [T]) -> usize {
+        let capacity = self.shared.buffer.len();
+        let tail = self.shared.tail.load(Ordering::Acquire);
+        let head = self.shared.head.load(Ordering::Relaxed);
+        let n = usize::min(tail.wrapping_sub(head), buffer.len());
+        for (i, x) in buffer[..n].iter_mut().enumerate() {
+            let slot = &self.shared.buffer[head.wrapping_add(i) % capacity];
+            *x = unsafe { *slot.get() };
+        }
+        self.shared.head.store(head.wrapping_add(n), Ordering::Release);
+        if n > 0 {
+            self.shared.notify();
+        }
+        n
+    }
+
+    /// Fill the buffer, waiting for the producer if there is not enough samples.
+    /// Returns number of read samples, which is less then the buffer size only
+    /// if the producer was dropped. Returns EndOfStream if there are no more samples.
+    pub fn read(&mut self, buffer: &mut [T]) -> Result<usize> {
+        let mut pos = 0;
+        while pos < buffer.len() {
+            // Check closed flag before reading, so the samples written just before
+            // the producer was dropped are not lost
+            let closed = self.is_closed();
+            let n = self.pop_slice(&mut buffer[pos..]);
+            if n == 0 {
+                if closed {
+                    break;
+                }
+                self.shared.wait_until(|s| s.len() > 0);
+            }
+            pos += n;
+        }
+        if pos == 0 && !buffer.is_empty() {
+            Err(Error::EndOfStream)
+        } else {
+            Ok(pos)
+        }
+    }
+
+    /// Fill the whole buffer, waiting for the producer if there is not enough samples.
+    /// Returns EndOfStream if the producer was dropped before the buffer was filled.
+    pub fn read_exact(&mut self, buffer: &mut [T]) -> Result<()> {
+        if self.read(buffer)? < buffer.len() {
+            Err(Error::EndOfStream)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// True if the producer was dropped
+    pub fn is_closed(&self) -> bool {
+        self.shared.is_closed()
+    }
+}
+
+impl<T> Drop for Consumer<T> {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
+        self.shared.notify();
+    }
+}
+
+
+/// ------------------------------------------------------------------------------------------------
+/// Module unit tests
+/// ------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use super::*;
+
+    #[test]
+    fn test_wrap_around() {
+        let (mut producer, mut consumer) = ring_buffer::<i32>(3).unwrap();
+        let mut buffer = vec![0; 2];
+        assert_eq!(producer.push_slice(&[1, 2, 3, 4]), 3);
+        assert_eq!(consumer.pop_slice(&mut buffer), 2);
+        assert_eq!(buffer, vec![1, 2]);
+        assert_eq!(producer.push_slice(&[5, 6, 7]), 2);
+        assert_eq!(consumer.pop_slice(&mut buffer), 2);
+        assert_eq!(buffer, vec![3, 5]);
+        assert_eq!(consumer.pop_slice(&mut buffer), 1);
+        assert_eq!(buffer[0], 6);
+        assert_eq!(consumer.pop_slice(&mut buffer), 0);
+    }
+
+    #[test]
+    fn test_end_of_stream() {
+        let (mut producer, mut consumer) = ring_buffer::<i32>(4).unwrap();
+        producer.write_all(&[1, 2, 3]).unwrap();
+        drop(producer);
+        let mut buffer = vec![0; 2];
+        assert!(consumer.read_exact(&mut buffer).is_ok());
+        assert_eq!(buffer, vec![1, 2]);
+        assert!(matches!(consumer.read_exact(&mut buffer), Err(Error::EndOfStream)));
+    }
+
+    #[test]
+    fn test_read_after_close() {
+        let (mut producer, mut consumer) = ring_buffer::<i32>(4).unwrap();
+        producer.write_all(&[1, 2, 3]).unwrap();
+        drop(producer);
+        let mut buffer = vec![0; 4];
+        assert_eq!(consumer.read(&mut buffer).unwrap(), 3);
+        assert_eq!(&buffer[..3], &[1, 2, 3]);
+        assert!(matches!(consumer.read(&mut buffer), Err(Error::EndOfStream)));
+    }
+
+    #[test]
+    fn test_consumer_dropped() {
+        let (mut producer, consumer) = ring_buffer::<i32>(4).unwrap();
+        drop(consumer);
+        assert!(matches!(producer.write_all(&[1, 2]), Err(Error::EndOfStream)));
+    }
+
+    #[test]
+    fn test_threads() {
+        let (mut producer, mut consumer) = ring_buffer::<usize>(7).unwrap();
+        let handle = thread::spawn(move || {
+            let data: Vec<usize> = (0..10_000).collect();
+            for chunk in data.chunks(13) {
+                producer.write_all(chunk).unwrap();
+            }
+        });
+        let mut buffer = vec![0; 10_000];
+        consumer.read_exact(&mut buffer).unwrap();
+        handle.join().unwrap();
+        assert!(buffer.iter().enumerate().all(|(i, v)| i == *v));
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        assert!(matches!(ring_buffer::<i32>(0), Err(Error::Config(_))));
+    }
+}
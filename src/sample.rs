@@ -0,0 +1,74 @@
+//! Sample types which can be processed by the filters
+//! 
+
+use std::ops::{Add, Mul, Sub};
+use num_complex::{Complex, Complex32, Complex64};
+use num_traits::Float;
+use rustfft::FftNum;
+
+
+/// Signal sample. Can be real or complex number. 
+/// Filters multiply samples by the real coefficients of type `Real`.
+pub trait Sample: 
+    Copy + Default + Send + 'static
+    + Add<Output = Self> 
+    + Sub<Output = Self> 
+    + Mul<Self::Real, Output = Self>
+{
+    /// Type of the filter coefficients
+    type Real: Float + FftNum + Default + Send + 'static;
+
+    /// Convert sample into complex number (real samples have zero imaginary part)
+    fn to_complex(self) -> Complex<Self::Real>;
+
+    /// Convert complex number into sample (real samples take the real part)
+    fn from_complex(c: Complex<Self::Real>) -> Self;
+}
+
+impl Sample for f32 {
+    type Real = f32;
+
+    fn to_complex(self) -> Complex<f32> {
+        Complex::new(self, 0.0)
+    }
+
+    fn from_complex(c: Complex<f32>) -> f32 {
+        c.re
+    }
+}
+
+impl Sample for Complex32 {
+    type Real = f32;
+
+    fn to_complex(self) -> Complex<f32> {
+        self
+    }
+
+    fn from_complex(c: Complex<f32>) -> Complex32 {
+        c
+    }
+}
+
+impl Sample for f64 {
+    type Real = f64;
+
+    fn to_complex(self) -> Complex<f64> {
+        Complex::new(self, 0.0)
+    }
+
+    fn from_complex(c: Complex<f64>) -> f64 {
+        c.re
+    }
+}
+
+impl Sample for Complex64 {
+    type Real = f64;
+
+    fn to_complex(self) -> Complex<f64> {
+        self
+    }
+
+    fn from_complex(c: Complex<f64>) -> Complex64 {
+        c
+    }
+}
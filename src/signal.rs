@@ -16,6 +16,7 @@ pub fn power(buffer: &[f32]) -> f32 {
 mod tests {
     use assert_approx_eq::assert_approx_eq;
     use crate::core::generator::Sine;
+    use crate::runtime::node::SourceNode;
     use super::*;
 
 
@@ -6,24 +6,76 @@ use crate::vector;
 
 
 /// Calculated frequency of a given component
+#[deprecated(note = "Use `fft_freq` which returns negative frequencies for the upper half of the spectrum")]
 pub fn item_freq(data_len: usize, sample_rate: usize, i: usize) -> f32 {
-    let pos = i % data_len;
-    let half_pos = if pos < data_len/2 { pos } else {data_len - pos};
-    (half_pos * sample_rate) as f32 / data_len as f32
+    fft_freq(data_len, sample_rate, i % data_len).abs()
+}
+
+/// Frequency of the bin i of the FFT with the given size.
+/// Bins above N/2 have negative frequencies.
+pub fn fft_freq(fft_size: usize, sample_rate: usize, i: usize) -> f32 {
+    let k = if 2 * i < fft_size { i as f32 } else { i as f32 - fft_size as f32 };
+    k * sample_rate as f32 / fft_size as f32
+}
+
+/// Frequencies of all bins of the FFT with the given size
+pub fn fft_freqs(fft_size: usize, sample_rate: usize) -> Vec<f32> {
+    (0..fft_size).map(|i| fft_freq(fft_size, sample_rate, i)).collect()
+}
+
+/// Frequencies of the N/2+1 bins of the real FFT with the given size
+pub fn rfft_freqs(fft_size: usize, sample_rate: usize) -> Vec<f32> {
+    (0..=fft_size / 2).map(|i| i as f32 * sample_rate as f32 / fft_size as f32).collect()
+}
+
+/// Move zero frequency bin to the center of the spectrum
+pub fn fftshift<T>(data: &mut [T]) {
+    let n = data.len();
+    data.rotate_right(n / 2);
+}
+
+/// Inverse of `fftshift`
+pub fn ifftshift<T>(data: &mut [T]) {
+    let n = data.len();
+    data.rotate_left(n / 2);
 }
 
 /// Return max frequency of spectrogram data
 pub fn max_freq(data: &[Complex32], sample_rate: usize) -> f32 {
     let buffer: Vec<f32> = data.iter().map(|v| v.norm()).collect();
     let idx = vector::argmax(&buffer);
-    if idx < data.len() / 2 {
-        item_freq(data.len(), sample_rate, idx)
-    } else {
-        item_freq(data.len(), sample_rate, data.len() - idx)
-    }
+    fft_freq(data.len(), sample_rate, idx).abs()
 }
 
-/// Calculate Decibels relative to the full scale
+/// One-sided amplitude spectrum of the real FFT bins.
+/// Sine with amplitude A gives value A in its bin (with rectangular window).
+///   * bins - N/2+1 bins from `RealFFT`
+///   * fft_size - FFT size N
+pub fn amplitude_spectrum(bins: &[Complex32], fft_size: usize) -> Vec<f32> {
+    bins.iter().enumerate()
+        .map(|(k, x)| x.norm() * one_sided_scale(k, fft_size) / fft_size as f32)
+        .collect()
+}
+
+/// One-sided power spectrum of the real FFT bins.
+/// The sum of all bins is equal to the mean square of the signal (with rectangular window).
+///   * bins - N/2+1 bins from `RealFFT`
+///   * fft_size - FFT size N
+pub fn power_spectrum(bins: &[Complex32], fft_size: usize) -> Vec<f32> {
+    let n2 = (fft_size * fft_size) as f32;
+    bins.iter().enumerate()
+        .map(|(k, x)| x.norm_sqr() * one_sided_scale(k, fft_size) / n2)
+        .collect()
+}
+
+/// Negative frequencies are folded into the positive ones, except DC and Nyquist bins
+fn one_sided_scale(k: usize, fft_size: usize) -> f32 {
+    if k == 0 || 2 * k == fft_size { 1.0 } else { 2.0 }
+}
+
+/// Calculate Decibels relative to the full scale.
+/// Spectrum should be calculated with `Normalization::Amplitude`,
+/// so the result doesn't depend on the FFT size and window.
 pub fn dbfs(sample: &Complex32, max_power: f32) -> f32 {
     20.0 * (sample.norm() / max_power).log10()
 }
@@ -34,10 +86,54 @@ pub fn dbfs(sample: &Complex32, max_power: f32) -> f32 {
 /// ------------------------------------------------------------------------------------------------
 #[cfg(test)]
 mod tests {
+    use assert_approx_eq::assert_approx_eq;
     use rustfft::num_traits::One;
-
+    use crate::core::fft::{RealFFT, WindowType};
+    use crate::runtime::node::ProcessNode;
     use super::*;
 
+    #[test]
+    fn test_fft_freq() {
+        assert_eq!(fft_freqs(4, 8), vec![0., 2., -4., -2.]);
+        assert_eq!(fft_freqs(5, 10), vec![0., 2., 4., -4., -2.]);
+        assert_eq!(rfft_freqs(4, 8), vec![0., 2., 4.]);
+        assert_eq!(rfft_freqs(5, 10), vec![0., 2., 4.]);
+    }
+
+    #[test]
+    fn test_fftshift() {
+        let mut freqs = fft_freqs(5, 10);
+        fftshift(&mut freqs);
+        assert_eq!(freqs, vec![-4., -2., 0., 2., 4.]);
+        ifftshift(&mut freqs);
+        assert_eq!(freqs, fft_freqs(5, 10));
+        let mut freqs = fft_freqs(4, 8);
+        fftshift(&mut freqs);
+        assert_eq!(freqs, vec![-4., -2., 0., 2.]);
+    }
+
+    #[test]
+    fn test_one_sided_spectrum() {
+        // DC 1.0 + sine with amplitude 2 at bin 4 + Nyquist 0.5
+        let n = 32;
+        let input: Vec<f32> = (0..n)
+            .map(|i| 1.0 + 2.0 * (2.0 * std::f32::consts::PI * 4.0 * i as f32 / n as f32).sin() 
+                + if i % 2 == 0 { 0.5 } else { -0.5 })
+            .collect();
+        let mut fft = RealFFT::new(n, WindowType::Rectangular);
+        let mut bins = vec![Complex32::default(); n / 2 + 1];
+        fft.process_buffer(&input, &mut bins).unwrap();
+        let amplitude = amplitude_spectrum(&bins, n);
+        assert_approx_eq!(amplitude[0], 1.0, 1e-5);
+        assert_approx_eq!(amplitude[4], 2.0, 1e-5);
+        assert_approx_eq!(amplitude[16], 0.5, 1e-5);
+        assert_approx_eq!(amplitude[5], 0.0, 1e-5);
+        // Parseval
+        let power: f32 = power_spectrum(&bins, n).iter().sum();
+        let mean_square = input.iter().map(|x| x * x).sum::<f32>() / n as f32;
+        assert_approx_eq!(power, mean_square, 1e-4);
+    }
+
     #[test]
     fn test_dbfs() {
         assert_eq!(dbfs(&Complex32::one(), 1.), 0.);
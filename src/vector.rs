@@ -20,8 +20,7 @@ where
 pub fn argmax(xs: &[f32]) -> usize {
     let mut max_value = f32::MIN;
     let mut arg_max = 0;
-    for i in 0..xs.len() {
-        let v = xs[i];
+    for (i, &v) in xs.iter().enumerate() {
         if max_value < v {
             max_value = v;
             arg_max = i;
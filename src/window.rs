@@ -16,9 +16,14 @@ impl Window {
         self.samples.len()
     }
 
+    /// Returns true if the window has no samples
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
     /// Apply this window to the given frame
-    pub fn apply(&self, input: &[f32], mut output: &mut [f32]) {
-        vector::multiply(&self.samples, &input, &mut output);
+    pub fn apply(&self, input: &[f32], output: &mut [f32]) {
+        vector::multiply(&self.samples, input, output);
     }
 
     pub fn as_slice(&self) -> &[f32] {
@@ -41,11 +46,7 @@ impl Window {
 /// assert_eq!(output, vec![1.0, 1.0, 1.0]);
 /// ```
 pub fn rectangular(width: usize) -> Window {
-    let mut samples = vec![0.0; width];
-    for i in 0..width {
-        samples[i] = 1.0;    
-    }
-    Window { samples }
+    Window { samples: vec![1.0; width] }
 }
 
 /// Creates a triangular window
@@ -64,9 +65,9 @@ pub fn rectangular(width: usize) -> Window {
 pub fn triangular(width: usize) -> Window {
     let mut samples = vec![0.0; width];
     let slope = 2.0 / ((width - 1) as f32);
-    for i in 0..width {
+    for (i, sample) in samples.iter_mut().enumerate() {
         let y = i as f32 * slope;
-        samples[i] = if i < width / 2 { y } else { 2.0 - y }    
+        *sample = if i < width / 2 { y } else { 2.0 - y }    
     }
     Window { samples }
 }
@@ -88,10 +89,10 @@ pub fn triangular(width: usize) -> Window {
 pub fn welch(width: usize) -> Window {
     let mut samples = vec![0.0; width];
     let half_width = (width-1) as f32 / 2.0;
-    for i in 0..width {
+    for (i, sample) in samples.iter_mut().enumerate() {
         let n = i as f32;
         let y = 1.0 - ((n - half_width) / half_width).powi(2);
-        samples[i] = y as f32;
+        *sample = y;
     }
     Window { samples }
 }
@@ -117,9 +118,9 @@ pub fn welch(width: usize) -> Window {
 /// ```
 pub fn sine(width: usize) -> Window {
     let mut samples = vec![0.0; width];
-    for i in 0..width {
+    for (i, sample) in samples.iter_mut().enumerate() {
         let n = i as f32;
-        samples[i] = (PI * n / (width - 1) as f32).sin();
+        *sample = (PI * n / (width - 1) as f32).sin();
     }
     Window { samples }
 }
@@ -145,9 +146,9 @@ pub fn sine(width: usize) -> Window {
 /// ```
 pub fn hann(width: usize) -> Window {
     let mut samples = vec![0.0; width];
-    for i in 0..width {
+    for (i, sample) in samples.iter_mut().enumerate() {
         let n = i as f32;
-        samples[i] = (PI * n / (width - 1) as f32).sin().powi(2);
+        *sample = (PI * n / (width - 1) as f32).sin().powi(2);
     }
     Window { samples }
 }
@@ -175,10 +176,10 @@ pub fn hamming(width: usize) -> Window {
     let a0 = 25.0 / 46.0;
     let mut samples = vec![0.0; width];
     let size = (width - 1) as f32;
-    for i in 0..width {
+    for (i, sample) in samples.iter_mut().enumerate() {
         let n = i as f32;
         let v = a0 - (1.0 - a0) * (2.0 * PI * n / size).cos();
-        samples[i] = v;
+        *sample = v;
     }
     Window { samples }
 }
@@ -208,15 +209,59 @@ pub fn blackman(width: usize) -> Window {
     let a2 = 1430.0 / 18608.0;
     let mut samples = vec![0.0; width];
     let size = (width - 1) as f32;
-    for i in 0..width {
+    for (i, sample) in samples.iter_mut().enumerate() {
         let n = i as f32;
         let v = a0 - a1 * (2.0 * PI * n / size).cos()
                     + a2 * (4.0 * PI * n / size).cos();
-        samples[i] = v;
+        *sample = v;
     }
     Window { samples }
 }
 
+/// Compute a Kaiser window
+/// https://en.wikipedia.org/wiki/Kaiser_window
+/// 
+/// Example
+/// 
+/// ```
+/// use assert_approx_eq::assert_approx_eq;
+/// use dsp::window;
+/// 
+/// let win = window::kaiser(5, 5.0);
+/// let frame = vec![1.0; 5];
+/// let mut output = vec![0.0; 5];
+/// win.apply(&frame, &mut output);
+/// assert_approx_eq!(output[0], 0.03671, 1e-5f32);
+/// assert_approx_eq!(output[1], 0.55285, 1e-5f32);
+/// assert_approx_eq!(output[2], 1.0, 1e-5f32);
+/// assert_approx_eq!(output[3], 0.55285, 1e-5f32);
+/// assert_approx_eq!(output[4], 0.03671, 1e-5f32);
+/// ```
+pub fn kaiser(width: usize, beta: f32) -> Window {
+    let mut samples = vec![0.0; width];
+    let beta = beta as f64;
+    let size = (width - 1) as f64;
+    let i0_beta = bessel_i0(beta);
+    for (i, sample) in samples.iter_mut().enumerate() {
+        let r = 2.0 * i as f64 / size - 1.0;
+        *sample = (bessel_i0(beta * (1.0 - r * r).sqrt()) / i0_beta) as f32;
+    }
+    Window { samples }
+}
+
+/// Modified Bessel function of the first kind of order 0
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1.0;
+    while term > 1e-12 * sum {
+        term *= (x / (2.0 * k)).powi(2);
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
 /// ------------------------------------------------------------------------------------------------
 /// Module unit tests
 /// ------------------------------------------------------------------------------------------------